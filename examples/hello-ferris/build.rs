@@ -0,0 +1,5 @@
+fn main() {
+    // The PDC is still checked in, since the Pebble SDK bundles resources
+    // before Cargo runs. This keeps it in sync with the SVG it comes from.
+    pebblesdk_build::svg::compile("../../media/ferris.svg", "resources/ferris.pdc");
+}
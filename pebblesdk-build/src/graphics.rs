@@ -0,0 +1,61 @@
+//! The parts of `pebblesdk::graphics` that don't depend on PebbleOS.
+//!
+//! `pebblesdk` can only be built for the watch, so the geometry types used by
+//! the PDC format are mirrored here for the host.
+
+#[path = "../../pebblesdk/src/graphics/pdc.rs"]
+pub mod pdc;
+
+/// An 8-bit color, with two bits each for alpha, red, green, and blue.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Color {
+    argb: u8,
+}
+
+impl Color {
+    pub const CLEAR: Color = Color::from_argb8(0b00000000);
+    pub const BLACK: Color = Color::from_argb8(0b11000000);
+    pub const WHITE: Color = Color::from_argb8(0b11111111);
+
+    /// Create a color from its packed `0bAARRGGBB` representation.
+    pub const fn from_argb8(argb: u8) -> Color {
+        Color { argb }
+    }
+
+    /// Create a color from 2-bit channels. Only the lowest two bits of each
+    /// channel are used.
+    pub const fn from_argb(alpha: u8, red: u8, green: u8, blue: u8) -> Color {
+        Color::from_argb8((alpha & 3) << 6 | (red & 3) << 4 | (green & 3) << 2 | (blue & 3))
+    }
+
+    /// The packed `0bAARRGGBB` representation of the color.
+    pub const fn argb8(self) -> u8 {
+        self.argb
+    }
+}
+
+/// A position on screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: i16,
+    pub y: i16,
+}
+
+impl Point {
+    pub const fn new(x: i16, y: i16) -> Point {
+        Point { x, y }
+    }
+}
+
+/// A width and height.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Size {
+    pub w: i16,
+    pub h: i16,
+}
+
+impl Size {
+    pub const fn new(w: i16, h: i16) -> Size {
+        Size { w, h }
+    }
+}
@@ -0,0 +1,28 @@
+//! Host-side helpers for building Pebble apps, meant to be used from build
+//! scripts.
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     pebblesdk_build::svg::compile("../../media/ferris.svg", "resources/ferris.pdc");
+//! }
+//! ```
+
+// The PDC module is shared with `pebblesdk`, which names `alloc` this way.
+extern crate alloc as rust_alloc;
+
+pub mod graphics;
+pub mod svg;
+#[path = "../../pebblesdk/src/trig.rs"]
+pub mod trig;
+
+// These parts of `pebblesdk::task` are only built to run their tests, with
+// `sys` standing in for PebbleOS.
+#[cfg(test)]
+#[path = "../../pebblesdk/src/task/executor.rs"]
+mod executor;
+#[cfg(test)]
+#[path = "../../pebblesdk/src/task/message.rs"]
+mod message;
+#[cfg(test)]
+mod sys;
@@ -0,0 +1,815 @@
+//! Converting SVG images to PDC.
+//!
+//! Only a subset of SVG maps onto draw commands:
+//!
+//! - `path`, `polyline`, `polygon`, `line`, and `rect` become paths, with
+//!   curves flattened into line segments and each subpath its own command.
+//! - `circle` (and `ellipse` with equal radii) becomes a circle.
+//! - `g`, `a`, and nested `svg` elements are flattened, applying their
+//!   transforms and inherited styles.
+//! - Fill and stroke colors are quantized to the 64 colors PebbleOS supports,
+//!   with anything less than half opaque becoming clear.
+//!
+//! Elements with `display: none`, and non-rendered elements such as `defs`,
+//! are skipped. Elements with `visibility: hidden` are kept, but marked hidden.
+//! Anything else, such as text, images, or gradients, is an error.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use roxmltree::{Document, Node};
+use svgtypes::{
+    Align, AspectRatio, Length, LengthUnit, Paint, PaintFallback, PointsParser, SimplePathSegment,
+    SimplifyingPathParser, Transform, ViewBox,
+};
+
+use crate::graphics::pdc::{Command, Image, Shape};
+use crate::graphics::{Color, Point, Size};
+
+const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+
+/// How many line segments each curve is flattened into.
+const CURVE_SEGMENTS: u32 = 8;
+
+/// Why an SVG image couldn't be converted.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Xml(roxmltree::Error),
+    /// An attribute is missing or has a value that couldn't be parsed.
+    InvalidAttribute {
+        element: String,
+        attribute: String,
+    },
+    /// The image uses a feature that can't be drawn with draw commands.
+    Unsupported(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "{error}"),
+            Error::Xml(error) => write!(f, "invalid SVG: {error}"),
+            Error::InvalidAttribute { element, attribute } => {
+                write!(f, "invalid `{attribute}` on <{element}>")
+            }
+            Error::Unsupported(feature) => write!(f, "unsupported in PDC: {feature}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::Xml(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+impl From<roxmltree::Error> for Error {
+    fn from(error: roxmltree::Error) -> Error {
+        Error::Xml(error)
+    }
+}
+
+/// Converts SVG images to PDC images.
+///
+/// ```ignore
+/// let image = Converter::new().precise(true).convert(&svg)?;
+/// std::fs::write("resources/logo.pdc", image.to_bytes())?;
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Converter {
+    precise: bool,
+}
+
+impl Converter {
+    pub fn new() -> Converter {
+        Converter::default()
+    }
+
+    /// Produce precise paths, with points in eighths of a pixel, instead of
+    /// rounding points to the pixel grid.
+    pub fn precise(mut self, precise: bool) -> Converter {
+        self.precise = precise;
+        self
+    }
+
+    /// Convert the text of an SVG image. The image takes the size of the root
+    /// element's `width` and `height`, with its `viewBox` scaled to fit them.
+    pub fn convert(&self, svg: &str) -> Result<Image, Error> {
+        let document = Document::parse(svg)?;
+        let root = document.root_element();
+        if !is_svg(root, "svg") {
+            return Err(Error::Unsupported(String::from("root element isn't <svg>")));
+        }
+
+        let (width, height) = (dimension(root, "width")?, dimension(root, "height")?);
+        let (size, transform) = match root.attribute("viewBox") {
+            Some(view_box) => {
+                let view_box = ViewBox::from_str(view_box).map_err(|_| invalid(root, "viewBox"))?;
+                if view_box.w <= 0.0 || view_box.h <= 0.0 {
+                    return Err(invalid(root, "viewBox"));
+                }
+                let aspect = match root.attribute("preserveAspectRatio") {
+                    Some(aspect) => AspectRatio::from_str(aspect)
+                        .map_err(|_| invalid(root, "preserveAspectRatio"))?,
+                    None => AspectRatio::default(),
+                };
+                // A missing width or height keeps the view box's aspect ratio.
+                let size = match (width, height) {
+                    (Some(w), Some(h)) => (w, h),
+                    (Some(w), None) => (w, w * view_box.h / view_box.w),
+                    (None, Some(h)) => (h * view_box.w / view_box.h, h),
+                    (None, None) => (view_box.w, view_box.h),
+                };
+                (size, fit_view_box(&view_box, size, aspect))
+            }
+            None => (
+                (width.unwrap_or(0.0), height.unwrap_or(0.0)),
+                Transform::default(),
+            ),
+        };
+
+        let mut image = Image {
+            view_box: Size::new(round(size.0), round(size.1)),
+            commands: Vec::new(),
+        };
+        let style = Style::default().inherit(root)?;
+        if !style.display_none {
+            self.children(root, &style, &transform, &mut image.commands)?;
+        }
+        Ok(image)
+    }
+
+    /// Convert the SVG file at `input`, writing a PDC file to `output`, and
+    /// tell Cargo to rerun the build script if the input changes.
+    pub fn convert_file(
+        &self,
+        input: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+    ) -> Result<(), Error> {
+        let input = input.as_ref();
+        println!("cargo:rerun-if-changed={}", input.display());
+        let image = self.convert(&fs::read_to_string(input)?)?;
+        let bytes = image.to_bytes();
+        // Leave the output untouched if it's current, so resource bundling
+        // isn't redone on every build.
+        if fs::read(output.as_ref()).ok().as_deref() != Some(&bytes[..]) {
+            fs::write(output, bytes)?;
+        }
+        Ok(())
+    }
+
+    fn children(
+        &self,
+        node: Node,
+        style: &Style,
+        transform: &Transform,
+        commands: &mut Vec<Command>,
+    ) -> Result<(), Error> {
+        for child in node.children().filter(|child| child.is_element()) {
+            self.element(child, style, transform, commands)?;
+        }
+        Ok(())
+    }
+
+    fn element(
+        &self,
+        node: Node,
+        style: &Style,
+        transform: &Transform,
+        commands: &mut Vec<Command>,
+    ) -> Result<(), Error> {
+        // Editors store their own data in other namespaces.
+        if node.tag_name().namespace() != Some(SVG_NAMESPACE) {
+            return Ok(());
+        }
+        let name = node.tag_name().name();
+        if matches!(
+            name,
+            "defs" | "title" | "desc" | "metadata" | "style" | "symbol" | "clipPath" | "mask"
+        ) {
+            return Ok(());
+        }
+
+        let style = style.inherit(node)?;
+        if style.display_none {
+            return Ok(());
+        }
+        let transform = match node.attribute("transform") {
+            Some(value) => {
+                let local = Transform::from_str(value).map_err(|_| invalid(node, "transform"))?;
+                multiply(transform, &local)
+            }
+            None => *transform,
+        };
+
+        match name {
+            "svg" | "g" | "a" => self.children(node, &style, &transform, commands),
+            "path" => {
+                let data = node.attribute("d").ok_or_else(|| invalid(node, "d"))?;
+                for (points, closed) in flatten_path(node, data)? {
+                    self.path(&points, closed, &style, &transform, commands);
+                }
+                Ok(())
+            }
+            "polyline" | "polygon" => {
+                let points = node
+                    .attribute("points")
+                    .ok_or_else(|| invalid(node, "points"))?;
+                let points: Vec<_> = PointsParser::from(points).collect();
+                self.path(&points, name == "polygon", &style, &transform, commands);
+                Ok(())
+            }
+            "line" => {
+                let points = [
+                    (length(node, "x1")?, length(node, "y1")?),
+                    (length(node, "x2")?, length(node, "y2")?),
+                ];
+                self.path(&points, false, &style, &transform, commands);
+                Ok(())
+            }
+            "rect" => {
+                let (x, y) = (length(node, "x")?, length(node, "y")?);
+                let (w, h) = (length(node, "width")?, length(node, "height")?);
+                let points = [(x, y), (x + w, y), (x + w, y + h), (x, y + h)];
+                self.path(&points, true, &style, &transform, commands);
+                Ok(())
+            }
+            "circle" => {
+                let center = (length(node, "cx")?, length(node, "cy")?);
+                self.circle(center, length(node, "r")?, &style, &transform, commands);
+                Ok(())
+            }
+            "ellipse" => {
+                let (rx, ry) = (length(node, "rx")?, length(node, "ry")?);
+                if rx != ry {
+                    return Err(Error::Unsupported(String::from(
+                        "ellipse with unequal radii",
+                    )));
+                }
+                let center = (length(node, "cx")?, length(node, "cy")?);
+                self.circle(center, rx, &style, &transform, commands);
+                Ok(())
+            }
+            name => Err(Error::Unsupported(format!("<{name}>"))),
+        }
+    }
+
+    fn path(
+        &self,
+        points: &[(f64, f64)],
+        closed: bool,
+        style: &Style,
+        transform: &Transform,
+        commands: &mut Vec<Command>,
+    ) {
+        let scale = if self.precise { 8.0 } else { 1.0 };
+        let mut converted: Vec<Point> = Vec::with_capacity(points.len());
+        for &point in points {
+            let (x, y) = apply(transform, point);
+            let point = Point::new(round(x * scale), round(y * scale));
+            if converted.last() != Some(&point) {
+                converted.push(point);
+            }
+        }
+        if closed && converted.len() > 1 && converted.first() == converted.last() {
+            converted.pop();
+        }
+        if converted.len() < 2 {
+            return;
+        }
+        let open = !closed;
+        let shape = if self.precise {
+            Shape::PrecisePath { open }
+        } else {
+            Shape::Path { open }
+        };
+        commands.push(style.command(shape, transform, converted));
+    }
+
+    fn circle(
+        &self,
+        center: (f64, f64),
+        radius: f64,
+        style: &Style,
+        transform: &Transform,
+        commands: &mut Vec<Command>,
+    ) {
+        let (x, y) = apply(transform, center);
+        let radius = (radius * scale(transform))
+            .round()
+            .clamp(0.0, u16::MAX as f64) as u16;
+        let shape = Shape::Circle { radius };
+        commands.push(style.command(shape, transform, vec![Point::new(round(x), round(y))]));
+    }
+}
+
+/// Convert the SVG file at `input` to a PDC file at `output`, for use from a
+/// build script.
+///
+/// # Panics
+///
+/// Panics with the reason if the image can't be converted, failing the build.
+pub fn compile(input: impl AsRef<Path>, output: impl AsRef<Path>) {
+    let input = input.as_ref();
+    if let Err(error) = Converter::new().convert_file(input, output) {
+        panic!("Failed to convert {}: {error}", input.display());
+    }
+}
+
+/// The presentation attributes that affect draw commands, as inherited down
+/// the tree.
+#[derive(Clone, Copy)]
+struct Style {
+    fill: Option<svgtypes::Color>,
+    fill_opacity: f64,
+    stroke: Option<svgtypes::Color>,
+    stroke_opacity: f64,
+    stroke_width: f64,
+    opacity: f64,
+    hidden: bool,
+    display_none: bool,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style {
+            fill: Some(svgtypes::Color::black()),
+            fill_opacity: 1.0,
+            stroke: None,
+            stroke_opacity: 1.0,
+            stroke_width: 1.0,
+            opacity: 1.0,
+            hidden: false,
+            display_none: false,
+        }
+    }
+}
+
+impl Style {
+    fn inherit(&self, node: Node) -> Result<Style, Error> {
+        let mut style = *self;
+        if let Some(value) = property(node, "fill") {
+            style.fill = paint(node, "fill", value, self.fill)?;
+        }
+        if let Some(value) = property(node, "stroke") {
+            style.stroke = paint(node, "stroke", value, self.stroke)?;
+        }
+        if let Some(value) = property(node, "fill-opacity") {
+            style.fill_opacity = number(node, "fill-opacity", value)?;
+        }
+        if let Some(value) = property(node, "stroke-opacity") {
+            style.stroke_opacity = number(node, "stroke-opacity", value)?;
+        }
+        if let Some(value) = property(node, "stroke-width") {
+            style.stroke_width = number(node, "stroke-width", value)?;
+        }
+        if let Some(value) = property(node, "opacity") {
+            style.opacity *= number(node, "opacity", value)?;
+        }
+        match property(node, "visibility") {
+            Some("visible") => style.hidden = false,
+            Some("hidden" | "collapse") => style.hidden = true,
+            _ => {}
+        }
+        style.display_none = property(node, "display") == Some("none");
+        Ok(style)
+    }
+
+    fn command(&self, shape: Shape, transform: &Transform, points: Vec<Point>) -> Command {
+        let stroke_color = quantize(self.stroke, self.stroke_opacity * self.opacity);
+        let stroke_width = if stroke_color == Color::CLEAR {
+            0
+        } else {
+            (self.stroke_width * scale(transform))
+                .round()
+                .clamp(0.0, 255.0) as u8
+        };
+        Command {
+            shape,
+            hidden: self.hidden,
+            stroke_color,
+            stroke_width,
+            fill_color: quantize(self.fill, self.fill_opacity * self.opacity),
+            points,
+        }
+    }
+}
+
+fn is_svg(node: Node, name: &str) -> bool {
+    node.tag_name().namespace() == Some(SVG_NAMESPACE) && node.tag_name().name() == name
+}
+
+fn invalid(node: Node, attribute: &str) -> Error {
+    Error::InvalidAttribute {
+        element: String::from(node.tag_name().name()),
+        attribute: String::from(attribute),
+    }
+}
+
+/// Look up a presentation property, preferring the `style` attribute.
+fn property<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    let declared = node.attribute("style").and_then(|style| {
+        style.split(';').rev().find_map(|declaration| {
+            let (key, value) = declaration.split_once(':')?;
+            (key.trim() == name).then(|| value.trim())
+        })
+    });
+    declared.or_else(|| node.attribute(name))
+}
+
+fn paint(
+    node: Node,
+    name: &str,
+    value: &str,
+    inherited: Option<svgtypes::Color>,
+) -> Result<Option<svgtypes::Color>, Error> {
+    match Paint::from_str(value).map_err(|_| invalid(node, name))? {
+        Paint::None => Ok(None),
+        Paint::Inherit => Ok(inherited),
+        Paint::Color(color) => Ok(Some(color)),
+        Paint::FuncIRI(_, Some(PaintFallback::Color(color))) => Ok(Some(color)),
+        Paint::FuncIRI(_, Some(PaintFallback::None)) => Ok(None),
+        _ => Err(Error::Unsupported(format!("`{name}: {value}`"))),
+    }
+}
+
+fn number(node: Node, name: &str, value: &str) -> Result<f64, Error> {
+    let length = Length::from_str(value).map_err(|_| invalid(node, name))?;
+    match length.unit {
+        LengthUnit::None | LengthUnit::Px => Ok(length.number),
+        LengthUnit::Percent if name.ends_with("opacity") => Ok(length.number / 100.0),
+        _ => Err(Error::Unsupported(format!(
+            "`{name}` in units other than pixels"
+        ))),
+    }
+}
+
+/// A length attribute, defaulting to zero.
+fn length(node: Node, name: &str) -> Result<f64, Error> {
+    node.attribute(name)
+        .map_or(Ok(0.0), |value| number(node, name, value))
+}
+
+/// The root element's `width` or `height`, unless it is missing or a
+/// percentage of the page.
+fn dimension(node: Node, name: &str) -> Result<Option<f64>, Error> {
+    let Some(value) = node.attribute(name) else {
+        return Ok(None);
+    };
+    match Length::from_str(value) {
+        Ok(length) if length.unit == LengthUnit::Percent => Ok(None),
+        _ => number(node, name, value).map(Some),
+    }
+}
+
+/// The transform from `view_box` to an image of `size`, scaled and aligned as
+/// `aspect` describes.
+fn fit_view_box(view_box: &ViewBox, size: (f64, f64), aspect: AspectRatio) -> Transform {
+    let (mut sx, mut sy) = (size.0 / view_box.w, size.1 / view_box.h);
+    if aspect.align != Align::None {
+        let scale = if aspect.slice { sx.max(sy) } else { sx.min(sy) };
+        (sx, sy) = (scale, scale);
+    }
+    // How much of the leftover space goes before the view box on each axis.
+    let (ax, ay) = match aspect.align {
+        Align::None | Align::XMinYMin => (0.0, 0.0),
+        Align::XMidYMin => (0.5, 0.0),
+        Align::XMaxYMin => (1.0, 0.0),
+        Align::XMinYMid => (0.0, 0.5),
+        Align::XMidYMid => (0.5, 0.5),
+        Align::XMaxYMid => (1.0, 0.5),
+        Align::XMinYMax => (0.0, 1.0),
+        Align::XMidYMax => (0.5, 1.0),
+        Align::XMaxYMax => (1.0, 1.0),
+    };
+    Transform::new(
+        sx,
+        0.0,
+        0.0,
+        sy,
+        (size.0 - view_box.w * sx) * ax - view_box.x * sx,
+        (size.1 - view_box.h * sy) * ay - view_box.y * sy,
+    )
+}
+
+/// The points of a subpath, and whether it is closed.
+type Subpath = (Vec<(f64, f64)>, bool);
+
+/// Split path data into subpaths.
+fn flatten_path(node: Node, data: &str) -> Result<Vec<Subpath>, Error> {
+    let mut subpaths = Vec::new();
+    let mut points: Vec<(f64, f64)> = Vec::new();
+    for segment in SimplifyingPathParser::from(data) {
+        let segment = segment.map_err(|_| invalid(node, "d"))?;
+        let current = points.last().copied().unwrap_or_default();
+        match segment {
+            SimplePathSegment::MoveTo { x, y } => {
+                if !points.is_empty() {
+                    subpaths.push((std::mem::take(&mut points), false));
+                }
+                points.push((x, y));
+            }
+            SimplePathSegment::LineTo { x, y } => points.push((x, y)),
+            SimplePathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => {
+                points.extend((1..=CURVE_SEGMENTS).map(|step| {
+                    let t = step as f64 / CURVE_SEGMENTS as f64;
+                    let u = 1.0 - t;
+                    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                    (
+                        a * current.0 + b * x1 + c * x2 + d * x,
+                        a * current.1 + b * y1 + c * y2 + d * y,
+                    )
+                }));
+            }
+            SimplePathSegment::Quadratic { x1, y1, x, y } => {
+                points.extend((1..=CURVE_SEGMENTS).map(|step| {
+                    let t = step as f64 / CURVE_SEGMENTS as f64;
+                    let u = 1.0 - t;
+                    let (a, b, c) = (u * u, 2.0 * u * t, t * t);
+                    (
+                        a * current.0 + b * x1 + c * x,
+                        a * current.1 + b * y1 + c * y,
+                    )
+                }));
+            }
+            SimplePathSegment::ClosePath => {
+                if !points.is_empty() {
+                    subpaths.push((std::mem::take(&mut points), true));
+                }
+            }
+        }
+    }
+    if !points.is_empty() {
+        subpaths.push((points, false));
+    }
+    Ok(subpaths)
+}
+
+fn quantize(color: Option<svgtypes::Color>, opacity: f64) -> Color {
+    match color {
+        Some(color) if color.alpha as f64 / 255.0 * opacity >= 0.5 => {
+            // Round each channel to the nearest of 0, 85, 170, and 255.
+            let level = |channel: u8| ((channel as u16 + 42) / 85) as u8;
+            Color::from_argb(3, level(color.red), level(color.green), level(color.blue))
+        }
+        _ => Color::CLEAR,
+    }
+}
+
+fn round(value: f64) -> i16 {
+    value.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16
+}
+
+/// The transform applying `inner` and then `outer`.
+fn multiply(outer: &Transform, inner: &Transform) -> Transform {
+    Transform::new(
+        outer.a * inner.a + outer.c * inner.b,
+        outer.b * inner.a + outer.d * inner.b,
+        outer.a * inner.c + outer.c * inner.d,
+        outer.b * inner.c + outer.d * inner.d,
+        outer.a * inner.e + outer.c * inner.f + outer.e,
+        outer.b * inner.e + outer.d * inner.f + outer.f,
+    )
+}
+
+fn apply(transform: &Transform, (x, y): (f64, f64)) -> (f64, f64) {
+    (
+        transform.a * x + transform.c * y + transform.e,
+        transform.b * x + transform.d * y + transform.f,
+    )
+}
+
+/// How much `transform` scales lengths, averaged over both axes.
+fn scale(transform: &Transform) -> f64 {
+    (transform.a * transform.d - transform.b * transform.c)
+        .abs()
+        .sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(svg: &str) -> Image {
+        Converter::new().convert(svg).unwrap()
+    }
+
+    fn svg(attributes: &str, body: &str) -> String {
+        format!(r#"<svg xmlns="http://www.w3.org/2000/svg" {attributes}>{body}</svg>"#)
+    }
+
+    fn points(image: &Image) -> Vec<Vec<(i16, i16)>> {
+        let command_points =
+            |command: &Command| command.points.iter().map(|p| (p.x, p.y)).collect();
+        image.commands.iter().map(command_points).collect()
+    }
+
+    #[test]
+    fn media_ferris() {
+        let image = convert(include_str!("../../media/ferris.svg"));
+        assert_eq!(image.view_box, Size::new(144, 168));
+        assert_eq!(image.commands.len(), 7);
+
+        // The background bleeds past the edges and has no outline.
+        let background = &image.commands[0];
+        assert_eq!(background.fill_color, Color::from_argb(3, 3, 2, 1));
+        assert_eq!(background.stroke_color, Color::CLEAR);
+        assert_eq!(background.stroke_width, 0);
+        assert_eq!(
+            points(&image)[0],
+            [(-6, -6), (150, -6), (150, 174), (-6, 174)]
+        );
+
+        // Ferris is outlined in black and fits on screen.
+        for command in &image.commands[1..] {
+            assert!(!command.hidden);
+            assert_eq!(command.stroke_color, Color::BLACK);
+            assert_eq!(command.stroke_width, 4);
+            assert!(command
+                .points
+                .iter()
+                .all(|point| { (0..144).contains(&point.x) && (0..168).contains(&point.y) }));
+        }
+
+        let shapes: Vec<_> = image.commands[1..].iter().map(|c| c.shape).collect();
+        let fills: Vec<_> = image.commands[1..].iter().map(|c| c.fill_color).collect();
+        let closed = Shape::Path { open: false };
+        assert_eq!(
+            shapes,
+            [
+                closed,
+                Shape::Path { open: true },
+                closed,
+                closed,
+                closed,
+                closed
+            ]
+        );
+        let (orange, clear, white) = (Color::from_argb(3, 3, 1, 0), Color::CLEAR, Color::WHITE);
+        assert_eq!(fills, [orange, clear, white, clear, white, clear]);
+        assert_eq!(image.commands[1].points.len(), 63);
+    }
+
+    #[test]
+    fn hello_ferris_is_current() {
+        let image = convert(include_str!("../../media/ferris.svg"));
+        let built = include_bytes!("../../examples/hello-ferris/resources/ferris.pdc");
+        assert_eq!(Image::parse(built).unwrap(), image);
+    }
+
+    #[test]
+    fn size_without_view_box() {
+        let image = convert(&svg(r#"width="30" height="20""#, ""));
+        assert_eq!(image.view_box, Size::new(30, 20));
+    }
+
+    #[test]
+    fn view_box_offset() {
+        let image = convert(&svg(
+            r#"viewBox="10 20 30 40""#,
+            r#"<rect x="10" y="20" width="30" height="40"/>"#,
+        ));
+        assert_eq!(image.view_box, Size::new(30, 40));
+        assert_eq!(points(&image), [[(0, 0), (30, 0), (30, 40), (0, 40)]]);
+    }
+
+    #[test]
+    fn view_box_scaled_to_width() {
+        let image = convert(&svg(
+            r#"viewBox="0 0 24 24" width="144""#,
+            r#"<rect width="24" height="12" stroke="black" stroke-width="2"/>"#,
+        ));
+        assert_eq!(image.view_box, Size::new(144, 144));
+        assert_eq!(points(&image), [[(0, 0), (144, 0), (144, 72), (0, 72)]]);
+        assert_eq!(image.commands[0].stroke_width, 12);
+    }
+
+    #[test]
+    fn view_box_scaled_to_percentage() {
+        let image = convert(&svg(r#"viewBox="0 0 24 24" width="100%" height="48""#, ""));
+        assert_eq!(image.view_box, Size::new(48, 48));
+    }
+
+    #[test]
+    fn view_box_centered() {
+        let image = convert(&svg(
+            r#"viewBox="0 0 10 10" width="20" height="40""#,
+            r#"<circle cx="5" cy="5" r="5"/>"#,
+        ));
+        assert_eq!(image.view_box, Size::new(20, 40));
+        assert_eq!(points(&image), [[(10, 20)]]);
+        assert_eq!(image.commands[0].shape, Shape::Circle { radius: 10 });
+    }
+
+    #[test]
+    fn view_box_aligned() {
+        let body = r#"<polyline points="0 0 10 10"/>"#;
+        let attributes = r#"viewBox="0 0 10 10" width="20" height="40""#;
+        let aligned = |aspect: &str| {
+            points(&convert(&svg(
+                &format!(r#"{attributes} preserveAspectRatio="{aspect}""#),
+                body,
+            )))
+        };
+        assert_eq!(aligned("xMinYMax"), [[(0, 20), (20, 40)]]);
+        assert_eq!(aligned("xMidYMid slice"), [[(-10, 0), (30, 40)]]);
+        assert_eq!(aligned("none"), [[(0, 0), (20, 40)]]);
+    }
+
+    #[test]
+    fn precise() {
+        let image = Converter::new()
+            .precise(true)
+            .convert(&svg(
+                r#"viewBox="0 0 10 10" width="5" height="5""#,
+                r#"<line x1="1" y1="1" x2="3" y2="3" stroke="black"/>"#,
+            ))
+            .unwrap();
+        assert_eq!(image.commands[0].shape, Shape::PrecisePath { open: true });
+        assert_eq!(points(&image), [[(4, 4), (12, 12)]]);
+    }
+
+    #[test]
+    fn styles() {
+        let image = convert(&svg(
+            r#"width="10" height="10" fill="red""#,
+            r##"<g style="stroke: #00f; stroke-width: 3">
+                <rect width="5" height="5"/>
+                <rect width="5" height="5" visibility="hidden" fill-opacity="0.4"/>
+                <rect width="5" height="5" display="none"/>
+                <rect width="5" height="5" stroke="none" fill="#0a0"/>
+            </g>"##,
+        ));
+        let styles: Vec<_> = image
+            .commands
+            .iter()
+            .map(|c| (c.hidden, c.fill_color, c.stroke_color, c.stroke_width))
+            .collect();
+        let (red, blue) = (Color::from_argb(3, 3, 0, 0), Color::from_argb(3, 0, 0, 3));
+        assert_eq!(
+            styles,
+            [
+                (false, red, blue, 3),
+                (true, Color::CLEAR, blue, 3),
+                (false, Color::from_argb(3, 0, 2, 0), Color::CLEAR, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn subpaths() {
+        let image = convert(&svg(
+            r#"width="10" height="10""#,
+            r#"<path d="M0 0 H4 V4 Z M6 6 L8 8"/>"#,
+        ));
+        let shapes: Vec<_> = image.commands.iter().map(|c| c.shape).collect();
+        assert_eq!(
+            shapes,
+            [Shape::Path { open: false }, Shape::Path { open: true }]
+        );
+        assert_eq!(
+            points(&image),
+            [vec![(0, 0), (4, 0), (4, 4)], vec![(6, 6), (8, 8)]]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |svg: &str| Converter::new().convert(svg).unwrap_err();
+        assert!(matches!(
+            error(&svg(r#"width="10" height="10""#, "<text>hi</text>")),
+            Error::Unsupported(_)
+        ));
+        assert!(matches!(
+            error(&svg(r#"viewBox="0 0 0 10""#, "")),
+            Error::InvalidAttribute { .. }
+        ));
+        assert!(matches!(
+            error(&svg(r#"width="1in" height="10""#, "")),
+            Error::Unsupported(_)
+        ));
+        assert!(matches!(error("<svg"), Error::Xml(_)));
+        assert!(matches!(error("<html/>"), Error::Unsupported(_)));
+    }
+}
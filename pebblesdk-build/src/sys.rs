@@ -0,0 +1,46 @@
+//! Stand-ins for the few PebbleOS functions used by the `pebblesdk` modules
+//! that are tested here.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::ffi::{c_uint, c_void};
+use std::ptr::NonNull;
+
+pub type TupleType = c_uint;
+pub const TUPLE_BYTE_ARRAY: TupleType = 0;
+pub const TUPLE_CSTRING: TupleType = 1;
+pub const TUPLE_UINT: TupleType = 2;
+pub const TUPLE_INT: TupleType = 3;
+
+pub enum AppTimer {}
+
+pub type AppTimerCallback = Option<unsafe extern "C" fn(data: *mut c_void)>;
+
+thread_local! {
+    static TIMERS: RefCell<VecDeque<(AppTimerCallback, *mut c_void)>> = const {
+        RefCell::new(VecDeque::new())
+    };
+}
+
+/// Queue the callback to be called by [`run_timers`], whatever the timeout.
+pub unsafe fn app_timer_register(
+    _timeout_ms: u32,
+    callback: AppTimerCallback,
+    callback_data: *mut c_void,
+) -> *mut AppTimer {
+    TIMERS.with(|timers| timers.borrow_mut().push_back((callback, callback_data)));
+    NonNull::dangling().as_ptr()
+}
+
+/// Call queued timer callbacks in order, including ones they register, until
+/// none are left, like the event loop would. Returns how many were called.
+pub fn run_timers() -> usize {
+    let mut count = 0;
+    while let Some((callback, data)) = TIMERS.with(|timers| timers.borrow_mut().pop_front()) {
+        if let Some(callback) = callback {
+            unsafe { callback(data) };
+        }
+        count += 1;
+    }
+    count
+}
@@ -0,0 +1,377 @@
+//! Animating layers and custom values over time.
+//!
+//! An [`Animation`] is owned by Rust until it is scheduled, at which point
+//! PebbleOS takes over and frees it (along with its closures) once it stops.
+
+use core::cell::RefCell;
+use core::ffi::c_void;
+use core::mem::ManuallyDrop;
+use core::ptr::{self, null_mut};
+
+use rust_alloc::boxed::Box;
+use rust_alloc::vec::Vec;
+
+use pebblesdk_sys::{
+    animation_create, animation_destroy, animation_get_context, animation_is_scheduled,
+    animation_schedule, animation_sequence_create_from_array, animation_set_curve,
+    animation_set_custom_curve, animation_set_delay, animation_set_duration,
+    animation_set_handlers, animation_set_implementation, animation_set_play_count,
+    animation_set_reverse, animation_spawn_create_from_array, animation_unschedule,
+    animation_unschedule_all, property_animation_create_layer_frame,
+    property_animation_get_animation, Animation as RawAnimation, AnimationCurveEaseIn,
+    AnimationCurveEaseInOut, AnimationCurveEaseOut, AnimationCurveLinear, AnimationHandlers,
+    AnimationImplementation, AnimationProgress, GRect, ANIMATION_DURATION_INFINITE,
+    ANIMATION_NORMALIZED_MAX, ANIMATION_PLAY_COUNT_INFINITE,
+};
+
+use crate::graphics::Rect;
+use crate::layer::Layer;
+use crate::timer::defer;
+
+/// How far through an animation's duration an update is, after the
+/// animation's [`Curve`] has been applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Progress(AnimationProgress);
+
+impl Progress {
+    pub const START: Progress = Progress(0);
+    pub const END: Progress = Progress(ANIMATION_NORMALIZED_MAX as AnimationProgress);
+
+    /// The progress scaled from 0 at the start to `u16::MAX` at the end.
+    /// Custom curves may overshoot either end.
+    pub const fn raw(self) -> AnimationProgress {
+        self.0
+    }
+
+    /// The progress scaled from 0.0 at the start to 1.0 at the end.
+    pub fn as_f32(self) -> f32 {
+        self.0 as f32 / Progress::END.0 as f32
+    }
+
+    /// The value this far between `from` and `to`.
+    pub fn lerp(self, from: i32, to: i32) -> i32 {
+        let delta = (to as i64 - from as i64) * self.0 as i64 / Progress::END.0 as i64;
+        (from as i64 + delta) as i32
+    }
+}
+
+/// How an animation's progress changes over its duration.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Curve {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut,
+    /// Map linear progress to curved progress, both scaled as in
+    /// [`Progress::raw`].
+    Custom(extern "C" fn(AnimationProgress) -> AnimationProgress),
+}
+
+/// Play an animation forever, with [`Animation::duration`] or
+/// [`Animation::play_count`].
+pub const INFINITE: u32 = ANIMATION_DURATION_INFINITE;
+
+const _: () = assert!(ANIMATION_DURATION_INFINITE == ANIMATION_PLAY_COUNT_INFINITE);
+
+type UpdateHandler = RefCell<Box<dyn FnMut(Progress)>>;
+type Handler<T> = RefCell<Option<Box<dyn FnMut(T)>>>;
+
+// The closures for one animation. Boxed so the context pointer given to
+// PebbleOS stays valid, and owned by the root of the animation's tree.
+#[derive(Default)]
+struct State {
+    update: Option<UpdateHandler>,
+    started: Handler<()>,
+    stopped: Handler<bool>,
+    // The states of the animations in a sequence or spawn. Never read, but
+    // each is the context of one of the animations, so must stay boxed.
+    #[allow(dead_code, clippy::vec_box)]
+    children: Vec<Box<State>>,
+    // Set once the state is owned by PebbleOS instead of an `Animation`.
+    scheduled: bool,
+}
+
+static IMPLEMENTATION: AnimationImplementation = AnimationImplementation {
+    setup: None,
+    update: Some(update),
+    teardown: None,
+};
+
+unsafe fn state<'a>(animation: *mut RawAnimation) -> &'a State {
+    unsafe { &*(animation_get_context(animation) as *const State) }
+}
+
+unsafe extern "C" fn update(animation: *mut RawAnimation, progress: AnimationProgress) {
+    if let Some(update) = &unsafe { state(animation) }.update {
+        if let Ok(mut update) = update.try_borrow_mut() {
+            update(Progress(progress));
+        }
+    }
+}
+
+unsafe extern "C" fn started(_animation: *mut RawAnimation, context: *mut c_void) {
+    let state = unsafe { &*(context as *const State) };
+    if let Ok(mut started) = state.started.try_borrow_mut() {
+        if let Some(started) = started.as_mut() {
+            started(());
+        }
+    }
+}
+
+unsafe extern "C" fn stopped(_animation: *mut RawAnimation, finished: bool, context: *mut c_void) {
+    let state = unsafe { &*(context as *const State) };
+    if let Ok(mut stopped) = state.stopped.try_borrow_mut() {
+        if let Some(stopped) = stopped.as_mut() {
+            stopped(finished);
+        }
+    }
+    if state.scheduled {
+        // Other callbacks in the tree may still be running (such as an update
+        // that unscheduled the animation), so free the closures once control
+        // returns to the event loop.
+        let state = context as *mut State;
+        defer(move || drop(unsafe { Box::from_raw(state) }));
+    }
+}
+
+/// An animation that hasn't been scheduled yet, destroyed if dropped.
+///
+/// ```ignore
+/// let slide = Animation::layer_frame(layer, None, Some(Rect::new(0, 0, 144, 40)))
+///     .duration(300)
+///     .curve(Curve::EaseOut);
+/// let fade = Animation::new(|progress| set_brightness(progress.lerp(0, 255)))
+///     .duration(500);
+/// Animation::sequence([slide, fade]).schedule();
+/// ```
+pub struct Animation {
+    raw: *mut RawAnimation,
+    state: Box<State>,
+}
+
+impl Animation {
+    fn from_raw(raw: *mut RawAnimation, state: State) -> Animation {
+        assert!(!raw.is_null(), "Failed to create animation");
+        let state = Box::new(state);
+        unsafe {
+            animation_set_handlers(
+                raw,
+                AnimationHandlers {
+                    started: Some(started),
+                    stopped: Some(stopped),
+                },
+                &*state as *const State as *mut c_void,
+            )
+        };
+        Animation { raw, state }
+    }
+
+    /// Create an animation that calls `update` with its progress each frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory.
+    pub fn new<F>(update: F) -> Animation
+    where
+        F: FnMut(Progress) + 'static,
+    {
+        let raw = unsafe { animation_create() };
+        let animation = Animation::from_raw(
+            raw,
+            State {
+                update: Some(RefCell::new(Box::new(update))),
+                ..State::default()
+            },
+        );
+        unsafe { animation_set_implementation(raw, &IMPLEMENTATION) };
+        animation
+    }
+
+    /// Create an animation moving `layer` from the frame `from` to the frame
+    /// `to`, where `None` is the layer's frame when the animation starts. The
+    /// layer must outlive the animation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory.
+    pub fn layer_frame(layer: &Layer, from: Option<Rect>, to: Option<Rect>) -> Animation {
+        let mut from = from.map(GRect::from);
+        let mut to = to.map(GRect::from);
+        let raw = unsafe {
+            let property_animation = property_animation_create_layer_frame(
+                layer.as_ptr(),
+                from.as_mut().map_or(null_mut(), |from| from),
+                to.as_mut().map_or(null_mut(), |to| to),
+            );
+            if property_animation.is_null() {
+                null_mut()
+            } else {
+                property_animation_get_animation(property_animation)
+            }
+        };
+        Animation::from_raw(raw, State::default())
+    }
+
+    fn combine<I, F>(animations: I, create: F) -> Animation
+    where
+        I: IntoIterator<Item = Animation>,
+        F: FnOnce(*mut *mut RawAnimation, u32) -> *mut RawAnimation,
+    {
+        let (mut raw, children): (Vec<_>, Vec<_>) = animations
+            .into_iter()
+            .map(|animation| {
+                let animation = ManuallyDrop::new(animation);
+                (animation.raw, unsafe { ptr::read(&animation.state) })
+            })
+            .unzip();
+        let combined = create(raw.as_mut_ptr(), raw.len() as u32);
+        if combined.is_null() {
+            for animation in raw {
+                unsafe { animation_destroy(animation) };
+            }
+        }
+        Animation::from_raw(
+            combined,
+            State {
+                children,
+                ..State::default()
+            },
+        )
+    }
+
+    /// Create an animation playing each of `animations` after the last.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory, or there are more than 20
+    /// animations.
+    pub fn sequence<I: IntoIterator<Item = Animation>>(animations: I) -> Animation {
+        Animation::combine(animations, |array, len| unsafe {
+            animation_sequence_create_from_array(array, len)
+        })
+    }
+
+    /// Create an animation playing all of `animations` at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory, or there are more than 20
+    /// animations.
+    pub fn spawn<I: IntoIterator<Item = Animation>>(animations: I) -> Animation {
+        Animation::combine(animations, |array, len| unsafe {
+            animation_spawn_create_from_array(array, len)
+        })
+    }
+
+    /// Get the underlying PebbleOS animation, for use with [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut RawAnimation {
+        self.raw
+    }
+
+    /// Set the length of one play of the animation, or [`INFINITE`]. Sequences
+    /// and spawns take their duration from their animations.
+    pub fn duration(self, duration_ms: u32) -> Animation {
+        unsafe { animation_set_duration(self.raw, duration_ms) };
+        self
+    }
+
+    /// Wait `delay_ms` milliseconds after being scheduled before starting.
+    pub fn delay(self, delay_ms: u32) -> Animation {
+        unsafe { animation_set_delay(self.raw, delay_ms) };
+        self
+    }
+
+    /// Set how progress changes over the duration. Has no effect on
+    /// sequences and spawns.
+    pub fn curve(self, curve: Curve) -> Animation {
+        unsafe {
+            match curve {
+                Curve::Linear => animation_set_curve(self.raw, AnimationCurveLinear),
+                Curve::EaseIn => animation_set_curve(self.raw, AnimationCurveEaseIn),
+                Curve::EaseOut => animation_set_curve(self.raw, AnimationCurveEaseOut),
+                Curve::EaseInOut => animation_set_curve(self.raw, AnimationCurveEaseInOut),
+                Curve::Custom(curve) => animation_set_custom_curve(self.raw, Some(curve)),
+            }
+        };
+        self
+    }
+
+    /// Play the animation `play_count` times, or [`INFINITE`]ly.
+    pub fn play_count(self, play_count: u32) -> Animation {
+        unsafe { animation_set_play_count(self.raw, play_count) };
+        self
+    }
+
+    /// Play the animation backwards.
+    pub fn reverse(self, reverse: bool) -> Animation {
+        unsafe { animation_set_reverse(self.raw, reverse) };
+        self
+    }
+
+    /// Call `handler` whenever the animation starts playing.
+    pub fn on_started<F: FnMut() + 'static>(self, mut handler: F) -> Animation {
+        *self.state.started.borrow_mut() = Some(Box::new(move |()| handler()));
+        self
+    }
+
+    /// Call `handler` whenever the animation stops, with whether it played to
+    /// the end rather than being unscheduled.
+    pub fn on_stopped<F: FnMut(bool) + 'static>(self, handler: F) -> Animation {
+        *self.state.stopped.borrow_mut() = Some(Box::new(handler));
+        self
+    }
+
+    /// Start the animation, handing it over to PebbleOS.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS refuses to schedule the animation.
+    pub fn schedule(self) -> ScheduledAnimation {
+        let animation = ManuallyDrop::new(self);
+        let raw = animation.raw;
+        let mut state = unsafe { ptr::read(&animation.state) };
+        state.scheduled = true;
+        // Only give up the state once PebbleOS has taken the animation, so it
+        // can still be freed below otherwise.
+        let state = Box::into_raw(state);
+        if !unsafe { animation_schedule(raw) } {
+            unsafe {
+                animation_destroy(raw);
+                drop(Box::from_raw(state));
+            }
+            panic!("Failed to schedule animation");
+        }
+        ScheduledAnimation { raw }
+    }
+}
+
+impl Drop for Animation {
+    fn drop(&mut self) {
+        // Destroying a sequence or spawn also destroys its animations.
+        unsafe { animation_destroy(self.raw) };
+    }
+}
+
+/// Refers to an animation that has been scheduled. PebbleOS frees the
+/// animation once it stops, after which this handle has no effect.
+#[derive(Clone, Copy, Debug)]
+pub struct ScheduledAnimation {
+    raw: *mut RawAnimation,
+}
+
+impl ScheduledAnimation {
+    pub fn is_scheduled(self) -> bool {
+        unsafe { animation_is_scheduled(self.raw) }
+    }
+
+    /// Stop the animation where it is, calling its stopped handler.
+    pub fn unschedule(self) {
+        unsafe { animation_unschedule(self.raw) };
+    }
+}
+
+/// Stop every scheduled animation.
+pub fn unschedule_all() {
+    unsafe { animation_unschedule_all() }
+}
@@ -0,0 +1,45 @@
+//! The entry point of a Pebble app.
+
+use pebblesdk_sys::app_event_loop;
+
+/// The state of an app, created before the event loop starts and dropped after
+/// it exits. Any teardown (destroying windows, unsubscribing from services)
+/// belongs in the type's `Drop` implementation.
+///
+/// Use [`pebble_app!`](crate::pebble_app) to make an `App` the program's entry
+/// point.
+pub trait App: Sized {
+    /// Set up the app, such as by creating and pushing its first window.
+    fn init() -> Self;
+}
+
+/// Initialize `A`, run the event loop until the app exits, then drop `A`.
+///
+/// This is what [`pebble_app!`](crate::pebble_app) calls from `main`, and
+/// shouldn't need to be called directly.
+pub fn run<A: App>() {
+    let app = A::init();
+    unsafe { app_event_loop() };
+    drop(app);
+}
+
+/// Define the `main` function of the app, running the given [`App`].
+///
+/// ```ignore
+/// struct HelloWorld { /* ... */ }
+///
+/// impl App for HelloWorld {
+///     fn init() -> Self { /* ... */ }
+/// }
+///
+/// pebble_app!(HelloWorld);
+/// ```
+#[macro_export]
+macro_rules! pebble_app {
+    ($app:ty) => {
+        #[unsafe(no_mangle)]
+        pub extern "C" fn main() {
+            $crate::app::run::<$app>()
+        }
+    };
+}
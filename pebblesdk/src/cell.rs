@@ -0,0 +1,87 @@
+//! Sharing app state with callbacks from PebbleOS.
+
+use core::cell::{BorrowError, BorrowMutError, Ref, RefCell, RefMut};
+
+/// A [`RefCell`] that can be stored in a `static`, so state can be reached from
+/// the `extern "C"` callbacks PebbleOS calls into.
+///
+/// Pebble apps run on a single thread, which makes it safe for `AppCell` to be
+/// `Sync` where a `RefCell` isn't. Borrows are still checked at runtime, so a
+/// callback that tries to mutably borrow state that is already borrowed will
+/// panic rather than alias it.
+///
+/// ```ignore
+/// static COUNTER: AppCell<u32> = AppCell::new(0);
+///
+/// extern "C" fn tick_handler(_tick_time: *mut tm, _units_changed: TimeUnits) {
+///     *COUNTER.borrow_mut() += 1;
+/// }
+/// ```
+pub struct AppCell<T> {
+    inner: RefCell<T>,
+}
+
+unsafe impl<T> Sync for AppCell<T> {}
+
+impl<T> AppCell<T> {
+    pub const fn new(value: T) -> AppCell<T> {
+        AppCell {
+            inner: RefCell::new(value),
+        }
+    }
+
+    /// Immutably borrow the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.inner.borrow()
+    }
+
+    /// Mutably borrow the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        self.inner.borrow_mut()
+    }
+
+    pub fn try_borrow(&self) -> Result<Ref<'_, T>, BorrowError> {
+        self.inner.try_borrow()
+    }
+
+    pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, BorrowMutError> {
+        self.inner.try_borrow_mut()
+    }
+
+    /// Call `f` with a mutable borrow of the value, returning its result.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.inner.borrow_mut())
+    }
+
+    /// Replace the value, returning the old one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn replace(&self, value: T) -> T {
+        self.inner.replace(value)
+    }
+}
+
+impl<T: Default> AppCell<T> {
+    /// Take the value, leaving `Default::default()` in its place.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn take(&self) -> T {
+        self.inner.take()
+    }
+}
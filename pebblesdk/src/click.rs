@@ -0,0 +1,353 @@
+//! Handling presses of the watch's buttons.
+
+use core::cell::RefCell;
+use core::ffi::c_void;
+use core::ops::RangeInclusive;
+
+use rust_alloc::boxed::Box;
+use rust_alloc::rc::Rc;
+
+use pebblesdk_sys::{
+    click_number_of_clicks_counted, click_recognizer_get_button_id, click_recognizer_is_repeating,
+    window_long_click_subscribe, window_multi_click_subscribe, window_raw_click_subscribe,
+    window_set_click_context, window_single_click_subscribe,
+    window_single_repeating_click_subscribe, ButtonId as RawButtonId, ClickRecognizerRef,
+    BUTTON_ID_BACK, BUTTON_ID_DOWN, BUTTON_ID_SELECT, BUTTON_ID_UP,
+};
+
+/// One of the four buttons on the watch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ButtonId {
+    Back,
+    Up,
+    Select,
+    Down,
+}
+
+impl ButtonId {
+    const ALL: [ButtonId; 4] = [
+        ButtonId::Back,
+        ButtonId::Up,
+        ButtonId::Select,
+        ButtonId::Down,
+    ];
+
+    pub(crate) fn raw(self) -> RawButtonId {
+        match self {
+            ButtonId::Back => BUTTON_ID_BACK,
+            ButtonId::Up => BUTTON_ID_UP,
+            ButtonId::Select => BUTTON_ID_SELECT,
+            ButtonId::Down => BUTTON_ID_DOWN,
+        }
+    }
+
+    pub(crate) fn from_raw(button: RawButtonId) -> Option<ButtonId> {
+        match button {
+            BUTTON_ID_BACK => Some(ButtonId::Back),
+            BUTTON_ID_UP => Some(ButtonId::Up),
+            BUTTON_ID_SELECT => Some(ButtonId::Select),
+            BUTTON_ID_DOWN => Some(ButtonId::Down),
+            _ => None,
+        }
+    }
+}
+
+/// The state of a button press, passed to click handlers. Only valid for the
+/// duration of the handler.
+pub struct ClickRecognizer {
+    raw: ClickRecognizerRef,
+}
+
+impl ClickRecognizer {
+    /// The button that was pressed.
+    pub fn button_id(&self) -> ButtonId {
+        ButtonId::from_raw(unsafe { click_recognizer_get_button_id(self.raw) })
+            .expect("PebbleOS reported an unknown button")
+    }
+
+    /// The number of clicks counted so far, such as 2 for a double click.
+    pub fn click_number(&self) -> u8 {
+        unsafe { click_number_of_clicks_counted(self.raw) }
+    }
+
+    /// True if the handler was called because the button is being held down.
+    pub fn is_repeating(&self) -> bool {
+        unsafe { click_recognizer_is_repeating(self.raw) }
+    }
+}
+
+type ClickHandler = RefCell<Box<dyn FnMut(&ClickRecognizer)>>;
+
+fn handler<F>(handler: F) -> ClickHandler
+where
+    F: FnMut(&ClickRecognizer) + 'static,
+{
+    RefCell::new(Box::new(handler))
+}
+
+struct MultiClick {
+    clicks: RangeInclusive<u8>,
+    timeout_ms: u16,
+    last_click_only: bool,
+    handler: ClickHandler,
+}
+
+struct LongClick {
+    delay_ms: u16,
+    down: ClickHandler,
+    up: ClickHandler,
+}
+
+#[derive(Default)]
+struct ButtonConfig {
+    single: Option<(u16, ClickHandler)>,
+    multi: Option<MultiClick>,
+    long: Option<LongClick>,
+    raw: Option<(ClickHandler, ClickHandler)>,
+}
+
+impl ButtonConfig {
+    fn is_empty(&self) -> bool {
+        self.single.is_none() && self.multi.is_none() && self.long.is_none() && self.raw.is_none()
+    }
+}
+
+/// A set of click handlers for a window, built up one button at a time.
+///
+/// ```ignore
+/// let config = ClickConfig::new()
+///     .single_click(ButtonId::Up, |_| counter.increment())
+///     .single_click(ButtonId::Down, |_| counter.decrement())
+///     .long_click(ButtonId::Select, 500, |_| counter.reset(), |_| {});
+/// window.set_click_config(config);
+/// ```
+#[derive(Default)]
+pub struct ClickConfig {
+    buttons: [ButtonConfig; 4],
+}
+
+impl ClickConfig {
+    pub fn new() -> ClickConfig {
+        ClickConfig::default()
+    }
+
+    fn button(&mut self, button: ButtonId) -> &mut ButtonConfig {
+        &mut self.buttons[button as usize]
+    }
+
+    /// Call `handler` when `button` is pressed and released.
+    ///
+    /// Subscribing to the back button overrides its default behavior of
+    /// popping the window.
+    pub fn single_click<F>(mut self, button: ButtonId, handler: F) -> ClickConfig
+    where
+        F: FnMut(&ClickRecognizer) + 'static,
+    {
+        self.button(button).single = Some((0, self::handler(handler)));
+        self
+    }
+
+    /// Call `handler` when `button` is pressed, and then every `repeat_ms`
+    /// milliseconds while it is held. Replaces any single click handler.
+    pub fn repeating_click<F>(mut self, button: ButtonId, repeat_ms: u16, handler: F) -> ClickConfig
+    where
+        F: FnMut(&ClickRecognizer) + 'static,
+    {
+        self.button(button).single = Some((repeat_ms, self::handler(handler)));
+        self
+    }
+
+    /// Call `handler` when `button` is pressed a number of times within
+    /// `clicks` in quick succession. A `timeout_ms` of 0 uses the system
+    /// default. If `last_click_only` is false, the handler is called for every
+    /// click counted within the range instead of only the last.
+    pub fn multi_click<F>(
+        mut self,
+        button: ButtonId,
+        clicks: RangeInclusive<u8>,
+        timeout_ms: u16,
+        last_click_only: bool,
+        handler: F,
+    ) -> ClickConfig
+    where
+        F: FnMut(&ClickRecognizer) + 'static,
+    {
+        self.button(button).multi = Some(MultiClick {
+            clicks,
+            timeout_ms,
+            last_click_only,
+            handler: self::handler(handler),
+        });
+        self
+    }
+
+    /// Call `down` when `button` has been held for `delay_ms` milliseconds
+    /// (or the system default if 0), and `up` when it is then released.
+    pub fn long_click<D, U>(
+        mut self,
+        button: ButtonId,
+        delay_ms: u16,
+        down: D,
+        up: U,
+    ) -> ClickConfig
+    where
+        D: FnMut(&ClickRecognizer) + 'static,
+        U: FnMut(&ClickRecognizer) + 'static,
+    {
+        self.button(button).long = Some(LongClick {
+            delay_ms,
+            down: handler(down),
+            up: handler(up),
+        });
+        self
+    }
+
+    /// Call `down` and `up` as soon as `button` is pressed and released,
+    /// without waiting to recognize a click.
+    pub fn raw_click<D, U>(mut self, button: ButtonId, down: D, up: U) -> ClickConfig
+    where
+        D: FnMut(&ClickRecognizer) + 'static,
+        U: FnMut(&ClickRecognizer) + 'static,
+    {
+        self.button(button).raw = Some((handler(down), handler(up)));
+        self
+    }
+}
+
+/// A [`ClickConfig`] that has been handed to PebbleOS as the context of
+/// [`click_config_provider`].
+///
+/// Click configs are reference counted so that a handler can safely replace
+/// the config it belongs to while it is running.
+pub(crate) struct SharedClickConfig(Rc<ClickConfig>);
+
+impl SharedClickConfig {
+    pub(crate) fn new(config: ClickConfig) -> SharedClickConfig {
+        SharedClickConfig(Rc::new(config))
+    }
+
+    pub(crate) fn context(&self) -> *mut c_void {
+        Rc::as_ptr(&self.0) as *mut c_void
+    }
+}
+
+/// Subscribe to every handler in a config. Meant to be called from within a
+/// `ClickConfigProvider`.
+///
+/// # Safety
+///
+/// `context` must come from [`SharedClickConfig::context`], and the
+/// `SharedClickConfig` must outlive the subscriptions.
+pub(crate) unsafe fn subscribe(context: *mut c_void) {
+    let config = unsafe { &*(context as *const ClickConfig) };
+    for button in ButtonId::ALL {
+        let button_config = &config.buttons[button as usize];
+        if button_config.is_empty() {
+            continue;
+        }
+        let id = button.raw();
+        unsafe {
+            if let Some((repeat_ms, _)) = &button_config.single {
+                if *repeat_ms == 0 {
+                    window_single_click_subscribe(id, Some(single_trampoline));
+                } else {
+                    window_single_repeating_click_subscribe(
+                        id,
+                        *repeat_ms,
+                        Some(single_trampoline),
+                    );
+                }
+            }
+            if let Some(multi) = &button_config.multi {
+                window_multi_click_subscribe(
+                    id,
+                    *multi.clicks.start(),
+                    *multi.clicks.end(),
+                    multi.timeout_ms,
+                    multi.last_click_only,
+                    Some(multi_trampoline),
+                );
+            }
+            if let Some(long) = &button_config.long {
+                window_long_click_subscribe(
+                    id,
+                    long.delay_ms,
+                    Some(long_down_trampoline),
+                    Some(long_up_trampoline),
+                );
+            }
+            if button_config.raw.is_some() {
+                window_raw_click_subscribe(
+                    id,
+                    Some(raw_down_trampoline),
+                    Some(raw_up_trampoline),
+                    context,
+                );
+            }
+            window_set_click_context(id, context);
+        }
+    }
+}
+
+pub(crate) unsafe extern "C" fn click_config_provider(context: *mut c_void) {
+    unsafe { subscribe(context) };
+}
+
+fn dispatch<F>(recognizer: ClickRecognizerRef, context: *mut c_void, select: F)
+where
+    F: FnOnce(&ButtonConfig) -> Option<&ClickHandler>,
+{
+    // Hold a reference for the duration of the handler, in case it replaces
+    // the config.
+    let config = unsafe {
+        Rc::increment_strong_count(context as *const ClickConfig);
+        Rc::from_raw(context as *const ClickConfig)
+    };
+    let recognizer = ClickRecognizer { raw: recognizer };
+    let Some(button) =
+        ButtonId::from_raw(unsafe { click_recognizer_get_button_id(recognizer.raw) })
+    else {
+        return;
+    };
+    if let Some(handler) = select(&config.buttons[button as usize]) {
+        if let Ok(mut handler) = handler.try_borrow_mut() {
+            handler(&recognizer);
+        }
+    }
+}
+
+unsafe extern "C" fn single_trampoline(recognizer: ClickRecognizerRef, context: *mut c_void) {
+    dispatch(recognizer, context, |button| {
+        button.single.as_ref().map(|(_, handler)| handler)
+    });
+}
+
+unsafe extern "C" fn multi_trampoline(recognizer: ClickRecognizerRef, context: *mut c_void) {
+    dispatch(recognizer, context, |button| {
+        button.multi.as_ref().map(|multi| &multi.handler)
+    });
+}
+
+unsafe extern "C" fn long_down_trampoline(recognizer: ClickRecognizerRef, context: *mut c_void) {
+    dispatch(recognizer, context, |button| {
+        button.long.as_ref().map(|long| &long.down)
+    });
+}
+
+unsafe extern "C" fn long_up_trampoline(recognizer: ClickRecognizerRef, context: *mut c_void) {
+    dispatch(recognizer, context, |button| {
+        button.long.as_ref().map(|long| &long.up)
+    });
+}
+
+unsafe extern "C" fn raw_down_trampoline(recognizer: ClickRecognizerRef, context: *mut c_void) {
+    dispatch(recognizer, context, |button| {
+        button.raw.as_ref().map(|(down, _)| down)
+    });
+}
+
+unsafe extern "C" fn raw_up_trampoline(recognizer: ClickRecognizerRef, context: *mut c_void) {
+    dispatch(recognizer, context, |button| {
+        button.raw.as_ref().map(|(_, up)| up)
+    });
+}
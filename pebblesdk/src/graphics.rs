@@ -0,0 +1,398 @@
+//! Geometry, colors, and drawing.
+
+use pebblesdk_sys::{
+    gpath_draw_filled, gpath_draw_outline, gpath_draw_outline_open,
+    graphics_context_set_antialiased, graphics_context_set_fill_color,
+    graphics_context_set_stroke_color, graphics_context_set_stroke_width,
+    graphics_context_set_text_color, graphics_draw_bitmap_in_rect, graphics_draw_circle,
+    graphics_draw_line, graphics_draw_pixel, graphics_draw_rect, graphics_draw_round_rect,
+    graphics_fill_circle, graphics_fill_rect, GAlign, GAlignBottom, GAlignBottomLeft,
+    GAlignBottomRight, GAlignCenter, GAlignLeft, GAlignRight, GAlignTop, GAlignTopLeft,
+    GAlignTopRight, GColor, GContext, GCornerNone, GCornersAll, GPoint, GRect, GSize,
+};
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+use pebblesdk_sys::{
+    gpoint_from_polar, graphics_draw_arc, graphics_fill_radial, grect_centered_from_polar,
+    GOvalScaleMode, GOvalScaleModeFillCircle, GOvalScaleModeFitCircle,
+};
+
+pub mod bitmap;
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+pub mod draw_command;
+pub mod font;
+pub mod framebuffer;
+pub mod path;
+pub mod pdc;
+pub mod text;
+
+pub use bitmap::{Bitmap, BitmapFormat};
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+pub use draw_command::{DrawCommandImage, DrawCommandSequence};
+pub use font::{CustomFont, Font, SystemFont};
+pub use framebuffer::Framebuffer;
+pub use path::Path;
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+pub use text::TextAttributes;
+pub use text::{TextAlignment, TextOverflowMode};
+
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+use crate::trig::Angle;
+
+/// An 8-bit color, with two bits each for alpha, red, green, and blue.
+///
+/// On black and white platforms, only [`Color::BLACK`], [`Color::WHITE`], and
+/// [`Color::CLEAR`] are displayed correctly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct Color {
+    argb: u8,
+}
+
+impl Color {
+    pub const CLEAR: Color = Color::from_argb8(0b00000000);
+    pub const BLACK: Color = Color::from_argb8(0b11000000);
+    pub const WHITE: Color = Color::from_argb8(0b11111111);
+
+    /// Create a color from its packed `0bAARRGGBB` representation.
+    pub const fn from_argb8(argb: u8) -> Color {
+        Color { argb }
+    }
+
+    /// Create a color from 2-bit channels. Only the lowest two bits of each
+    /// channel are used.
+    pub const fn from_argb(alpha: u8, red: u8, green: u8, blue: u8) -> Color {
+        Color::from_argb8((alpha & 3) << 6 | (red & 3) << 4 | (green & 3) << 2 | (blue & 3))
+    }
+
+    /// Create an opaque color from 8-bit channels, rounding each down to the
+    /// nearest of the four levels PebbleOS supports.
+    pub const fn from_rgb(red: u8, green: u8, blue: u8) -> Color {
+        Color::from_argb(3, red >> 6, green >> 6, blue >> 6)
+    }
+
+    /// The packed `0bAARRGGBB` representation of the color.
+    pub const fn argb8(self) -> u8 {
+        self.argb
+    }
+
+    pub(crate) fn raw(self) -> GColor {
+        GColor { argb: self.argb }
+    }
+
+    pub(crate) fn from_raw(color: GColor) -> Color {
+        Color::from_argb8(unsafe { color.argb })
+    }
+}
+
+/// A position on screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: i16,
+    pub y: i16,
+}
+
+impl Point {
+    pub const fn new(x: i16, y: i16) -> Point {
+        Point { x, y }
+    }
+}
+
+impl From<GPoint> for Point {
+    fn from(point: GPoint) -> Point {
+        Point::new(point.x, point.y)
+    }
+}
+
+impl From<Point> for GPoint {
+    fn from(point: Point) -> GPoint {
+        GPoint {
+            x: point.x,
+            y: point.y,
+        }
+    }
+}
+
+/// A width and height.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Size {
+    pub w: i16,
+    pub h: i16,
+}
+
+impl Size {
+    pub const fn new(w: i16, h: i16) -> Size {
+        Size { w, h }
+    }
+}
+
+impl From<GSize> for Size {
+    fn from(size: GSize) -> Size {
+        Size::new(size.w, size.h)
+    }
+}
+
+impl From<Size> for GSize {
+    fn from(size: Size) -> GSize {
+        GSize {
+            w: size.w,
+            h: size.h,
+        }
+    }
+}
+
+/// A rectangle, given by its top left corner and size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub origin: Point,
+    pub size: Size,
+}
+
+impl Rect {
+    pub const fn new(x: i16, y: i16, w: i16, h: i16) -> Rect {
+        Rect {
+            origin: Point::new(x, y),
+            size: Size::new(w, h),
+        }
+    }
+
+    /// The point in the middle of the rectangle, rounded towards the origin.
+    pub const fn center(self) -> Point {
+        Point::new(
+            self.origin.x + self.size.w / 2,
+            self.origin.y + self.size.h / 2,
+        )
+    }
+
+    /// The point on the edge of the oval fitted into the rectangle by `mode`,
+    /// at `angle` clockwise from 12 o'clock.
+    #[cfg(not(pebble_sdk_platform = "aplite"))]
+    pub fn point_from_polar(self, mode: OvalScaleMode, angle: Angle) -> Point {
+        unsafe { gpoint_from_polar(self.into(), mode.raw(), angle.raw()) }.into()
+    }
+
+    /// A rectangle of `size` centered on [`point_from_polar`], such as for
+    /// placing hour markers around a round display.
+    ///
+    /// [`point_from_polar`]: Rect::point_from_polar
+    #[cfg(not(pebble_sdk_platform = "aplite"))]
+    pub fn centered_from_polar(self, mode: OvalScaleMode, angle: Angle, size: Size) -> Rect {
+        unsafe { grect_centered_from_polar(self.into(), mode.raw(), angle.raw(), size.into()) }
+            .into()
+    }
+}
+
+impl From<GRect> for Rect {
+    fn from(rect: GRect) -> Rect {
+        Rect {
+            origin: rect.origin.into(),
+            size: rect.size.into(),
+        }
+    }
+}
+
+impl From<Rect> for GRect {
+    fn from(rect: Rect) -> GRect {
+        GRect {
+            origin: rect.origin.into(),
+            size: rect.size.into(),
+        }
+    }
+}
+
+/// Where to place something within a larger area.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Align {
+    Center,
+    TopLeft,
+    TopRight,
+    Top,
+    Left,
+    Bottom,
+    Right,
+    BottomRight,
+    BottomLeft,
+}
+
+impl Align {
+    pub(crate) fn raw(self) -> GAlign {
+        match self {
+            Align::Center => GAlignCenter,
+            Align::TopLeft => GAlignTopLeft,
+            Align::TopRight => GAlignTopRight,
+            Align::Top => GAlignTop,
+            Align::Left => GAlignLeft,
+            Align::Bottom => GAlignBottom,
+            Align::Right => GAlignRight,
+            Align::BottomRight => GAlignBottomRight,
+            Align::BottomLeft => GAlignBottomLeft,
+        }
+    }
+}
+
+/// How an oval is fitted into a rectangle, for the polar drawing methods.
+///
+/// Both fit a circle when the rectangle is square.
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OvalScaleMode {
+    /// The largest circle that fits inside the rectangle, centered in it.
+    #[default]
+    FitCircle,
+    /// The smallest circle that covers the rectangle, centered on it.
+    FillCircle,
+}
+
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+impl OvalScaleMode {
+    fn raw(self) -> GOvalScaleMode {
+        match self {
+            OvalScaleMode::FitCircle => GOvalScaleModeFitCircle,
+            OvalScaleMode::FillCircle => GOvalScaleModeFillCircle,
+        }
+    }
+}
+
+/// The drawing state passed to update procs and other drawing callbacks.
+///
+/// Only ever handled by reference, since PebbleOS owns the context.
+#[repr(transparent)]
+pub struct GraphicsContext(GContext);
+
+impl GraphicsContext {
+    /// # Safety
+    ///
+    /// `ctx` must be a valid graphics context for the lifetime `'a`, such as
+    /// the one passed to a drawing callback while the callback runs.
+    pub(crate) unsafe fn from_ptr<'a>(ctx: *mut GContext) -> &'a mut GraphicsContext {
+        unsafe { &mut *(ctx as *mut GraphicsContext) }
+    }
+
+    /// Get the underlying PebbleOS context, for use with [`crate::sys`].
+    pub fn as_ptr(&mut self) -> *mut GContext {
+        self as *mut GraphicsContext as *mut GContext
+    }
+
+    pub fn set_stroke_color(&mut self, color: Color) {
+        unsafe { graphics_context_set_stroke_color(self.as_ptr(), color.raw()) }
+    }
+
+    pub fn set_fill_color(&mut self, color: Color) {
+        unsafe { graphics_context_set_fill_color(self.as_ptr(), color.raw()) }
+    }
+
+    pub fn set_text_color(&mut self, color: Color) {
+        unsafe { graphics_context_set_text_color(self.as_ptr(), color.raw()) }
+    }
+
+    /// Set the width of lines and outlines. Only odd widths are supported, and
+    /// even widths are rounded down.
+    pub fn set_stroke_width(&mut self, width: u8) {
+        unsafe { graphics_context_set_stroke_width(self.as_ptr(), width) }
+    }
+
+    pub fn set_antialiased(&mut self, enable: bool) {
+        unsafe { graphics_context_set_antialiased(self.as_ptr(), enable) }
+    }
+
+    pub fn draw_pixel(&mut self, point: Point) {
+        unsafe { graphics_draw_pixel(self.as_ptr(), point.into()) }
+    }
+
+    pub fn draw_line(&mut self, start: Point, end: Point) {
+        unsafe { graphics_draw_line(self.as_ptr(), start.into(), end.into()) }
+    }
+
+    pub fn draw_rect(&mut self, rect: Rect) {
+        unsafe { graphics_draw_rect(self.as_ptr(), rect.into()) }
+    }
+
+    pub fn draw_round_rect(&mut self, rect: Rect, radius: u16) {
+        unsafe { graphics_draw_round_rect(self.as_ptr(), rect.into(), radius) }
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect) {
+        unsafe { graphics_fill_rect(self.as_ptr(), rect.into(), 0, GCornerNone) }
+    }
+
+    pub fn fill_round_rect(&mut self, rect: Rect, radius: u16) {
+        unsafe { graphics_fill_rect(self.as_ptr(), rect.into(), radius, GCornersAll) }
+    }
+
+    pub fn draw_circle(&mut self, center: Point, radius: u16) {
+        unsafe { graphics_draw_circle(self.as_ptr(), center.into(), radius) }
+    }
+
+    pub fn fill_circle(&mut self, center: Point, radius: u16) {
+        unsafe { graphics_fill_circle(self.as_ptr(), center.into(), radius) }
+    }
+
+    /// Draw a bitmap, tiling it if `rect` is larger than the bitmap.
+    pub fn draw_bitmap_in_rect(&mut self, bitmap: &Bitmap, rect: Rect) {
+        unsafe { graphics_draw_bitmap_in_rect(self.as_ptr(), bitmap.as_ptr(), rect.into()) }
+    }
+
+    /// Draw the part of the oval fitted into `rect` between two angles,
+    /// clockwise from 12 o'clock, with the stroke color and width.
+    #[cfg(not(pebble_sdk_platform = "aplite"))]
+    pub fn draw_arc(&mut self, rect: Rect, mode: OvalScaleMode, start: Angle, end: Angle) {
+        unsafe {
+            graphics_draw_arc(
+                self.as_ptr(),
+                rect.into(),
+                mode.raw(),
+                start.raw(),
+                end.raw(),
+            )
+        }
+    }
+
+    /// Fill the part of the oval fitted into `rect` between two angles, to
+    /// `thickness` pixels in from its edge, with the fill color. A thickness
+    /// of at least half the oval's size fills a pie slice.
+    #[cfg(not(pebble_sdk_platform = "aplite"))]
+    pub fn fill_radial(
+        &mut self,
+        rect: Rect,
+        mode: OvalScaleMode,
+        thickness: u16,
+        start: Angle,
+        end: Angle,
+    ) {
+        unsafe {
+            graphics_fill_radial(
+                self.as_ptr(),
+                rect.into(),
+                mode.raw(),
+                thickness,
+                start.raw(),
+                end.raw(),
+            )
+        }
+    }
+
+    /// Fill the area enclosed by `path`.
+    pub fn draw_path_filled(&mut self, path: &Path) {
+        unsafe { gpath_draw_filled(self.as_ptr(), path.as_ptr()) }
+    }
+
+    /// Draw the outline of `path`, closed back to its first point.
+    pub fn draw_path_outline(&mut self, path: &Path) {
+        unsafe { gpath_draw_outline(self.as_ptr(), path.as_ptr()) }
+    }
+
+    /// Draw the outline of `path` without closing it.
+    pub fn draw_path_outline_open(&mut self, path: &Path) {
+        unsafe { gpath_draw_outline_open(self.as_ptr(), path.as_ptr()) }
+    }
+
+    /// Capture the screen's pixels for direct access, releasing them once `f`
+    /// returns. Returns `None` if they can't be captured, such as when they
+    /// already are.
+    pub fn with_framebuffer<R, F>(&mut self, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Framebuffer) -> R,
+    {
+        let mut framebuffer = Framebuffer::capture(self)?;
+        Some(f(&mut framebuffer))
+    }
+}
@@ -0,0 +1,542 @@
+//! Owned images, and access to their pixels.
+
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::slice;
+
+use rust_alloc::boxed::Box;
+
+use pebblesdk_sys::{
+    gbitmap_create_as_sub_bitmap, gbitmap_create_blank, gbitmap_create_with_resource,
+    gbitmap_destroy, gbitmap_get_bounds, gbitmap_get_bytes_per_row, gbitmap_get_data,
+    gbitmap_get_format, gbitmap_get_palette, gbitmap_set_palette, GBitmap, GBitmapFormat,
+    GBitmapFormat1Bit, GBitmapFormat1BitPalette, GBitmapFormat2BitPalette,
+    GBitmapFormat4BitPalette, GBitmapFormat8Bit, GBitmapFormat8BitCircular, GColor,
+};
+
+use crate::graphics::{Color, Point, Rect, Size};
+
+/// How the pixels of a [`Bitmap`] are stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitmapFormat {
+    /// One bit per pixel, set for white, with the leftmost pixel in the least
+    /// significant bit.
+    OneBit,
+    /// One [`Color`] per byte.
+    EightBit,
+    /// One bit per pixel, indexing a palette of two colors.
+    OneBitPalette,
+    /// Two bits per pixel, indexing a palette of four colors.
+    TwoBitPalette,
+    /// Four bits per pixel, indexing a palette of sixteen colors.
+    FourBitPalette,
+    /// One [`Color`] per byte, with each row only as wide as the round
+    /// display at that height.
+    EightBitCircular,
+}
+
+impl BitmapFormat {
+    fn raw(self) -> GBitmapFormat {
+        match self {
+            BitmapFormat::OneBit => GBitmapFormat1Bit,
+            BitmapFormat::EightBit => GBitmapFormat8Bit,
+            BitmapFormat::OneBitPalette => GBitmapFormat1BitPalette,
+            BitmapFormat::TwoBitPalette => GBitmapFormat2BitPalette,
+            BitmapFormat::FourBitPalette => GBitmapFormat4BitPalette,
+            BitmapFormat::EightBitCircular => GBitmapFormat8BitCircular,
+        }
+    }
+
+    #[allow(non_upper_case_globals)]
+    fn from_raw(format: GBitmapFormat) -> BitmapFormat {
+        match format {
+            GBitmapFormat1Bit => BitmapFormat::OneBit,
+            GBitmapFormat8Bit => BitmapFormat::EightBit,
+            GBitmapFormat1BitPalette => BitmapFormat::OneBitPalette,
+            GBitmapFormat2BitPalette => BitmapFormat::TwoBitPalette,
+            GBitmapFormat4BitPalette => BitmapFormat::FourBitPalette,
+            GBitmapFormat8BitCircular => BitmapFormat::EightBitCircular,
+            _ => panic!("Unknown bitmap format"),
+        }
+    }
+
+    pub const fn bits_per_pixel(self) -> u8 {
+        match self {
+            BitmapFormat::OneBit | BitmapFormat::OneBitPalette => 1,
+            BitmapFormat::TwoBitPalette => 2,
+            BitmapFormat::FourBitPalette => 4,
+            BitmapFormat::EightBit | BitmapFormat::EightBitCircular => 8,
+        }
+    }
+
+    /// The number of colors in the palette, or 0 if the format isn't
+    /// palettized.
+    pub const fn palette_size(self) -> usize {
+        match self {
+            BitmapFormat::OneBitPalette | BitmapFormat::TwoBitPalette => 1 << self.bits_per_pixel(),
+            BitmapFormat::FourBitPalette => 16,
+            _ => 0,
+        }
+    }
+}
+
+/// An owned PebbleOS bitmap, destroyed when dropped.
+pub struct Bitmap {
+    raw: *mut GBitmap,
+    // Set by `set_palette`. PebbleOS only borrows it.
+    palette: Option<Box<[Color]>>,
+}
+
+impl Bitmap {
+    pub(crate) fn from_raw(raw: *mut GBitmap) -> Option<Bitmap> {
+        (!raw.is_null()).then_some(Bitmap { raw, palette: None })
+    }
+
+    /// Load a bitmap from an image resource. Returns `None` if the resource
+    /// isn't an image or there isn't enough memory to load it.
+    pub fn with_resource(resource_id: u32) -> Option<Bitmap> {
+        Bitmap::from_raw(unsafe { gbitmap_create_with_resource(resource_id) })
+    }
+
+    /// Create a bitmap with every pixel zeroed. Palettized formats also get a
+    /// palette of the right size. Returns `None` if there isn't enough
+    /// memory.
+    pub fn blank(size: Size, format: BitmapFormat) -> Option<Bitmap> {
+        Bitmap::from_raw(unsafe { gbitmap_create_blank(size.into(), format.raw()) })
+    }
+
+    /// Create a bitmap showing the part of `self` within `rect`, sharing its
+    /// pixels and palette. Returns `None` if there isn't enough memory.
+    pub fn sub_bitmap(&self, rect: Rect) -> Option<SubBitmap<'_>> {
+        let raw = unsafe { gbitmap_create_as_sub_bitmap(self.raw, rect.into()) };
+        Bitmap::from_raw(raw).map(|bitmap| SubBitmap {
+            bitmap,
+            _base: PhantomData,
+        })
+    }
+
+    /// Get the underlying PebbleOS bitmap, for use with [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut GBitmap {
+        self.raw
+    }
+
+    /// The area of the bitmap's data that is drawn.
+    pub fn bounds(&self) -> Rect {
+        unsafe { gbitmap_get_bounds(self.raw) }.into()
+    }
+
+    pub fn format(&self) -> BitmapFormat {
+        BitmapFormat::from_raw(unsafe { gbitmap_get_format(self.raw) })
+    }
+
+    /// The number of bytes between the start of one row and the next.
+    /// Meaningless for [`BitmapFormat::EightBitCircular`].
+    pub fn bytes_per_row(&self) -> u16 {
+        unsafe { gbitmap_get_bytes_per_row(self.raw) }
+    }
+
+    fn data_len(&self) -> usize {
+        let bounds = self.bounds();
+        (bounds.origin.y + bounds.size.h) as usize * self.bytes_per_row() as usize
+    }
+
+    /// The raw pixel data, from the first row up to the bottom of the bounds.
+    /// Returns `None` for [`BitmapFormat::EightBitCircular`], whose rows
+    /// aren't evenly spaced; use [`rows`](Bitmap::rows) instead.
+    pub fn data(&self) -> Option<&[u8]> {
+        if self.format() == BitmapFormat::EightBitCircular {
+            return None;
+        }
+        let data = unsafe { gbitmap_get_data(self.raw) };
+        Some(unsafe { slice::from_raw_parts(data, self.data_len()) })
+    }
+
+    pub fn data_mut(&mut self) -> Option<&mut [u8]> {
+        if self.format() == BitmapFormat::EightBitCircular {
+            return None;
+        }
+        let data = unsafe { gbitmap_get_data(self.raw) };
+        Some(unsafe { slice::from_raw_parts_mut(data, self.data_len()) })
+    }
+
+    /// The colors indexed by a palettized bitmap's pixels.
+    pub fn palette(&self) -> Option<&[Color]> {
+        unsafe { palette(self.raw) }.map(|palette| &*palette)
+    }
+
+    pub fn palette_mut(&mut self) -> Option<&mut [Color]> {
+        unsafe { palette(self.raw) }
+    }
+
+    /// Replace the palette of a palettized bitmap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bitmap isn't palettized, or `palette` doesn't have
+    /// exactly [`BitmapFormat::palette_size`] colors.
+    pub fn set_palette(&mut self, palette: Box<[Color]>) {
+        let size = self.format().palette_size();
+        assert!(size > 0, "Bitmap isn't palettized");
+        assert_eq!(palette.len(), size, "Palette is the wrong size");
+        let palette = self.palette.insert(palette);
+        unsafe { gbitmap_set_palette(self.raw, palette.as_mut_ptr() as *mut GColor, false) };
+    }
+
+    /// The row at `y`, or `None` if it's outside the bounds.
+    pub fn row(&self, y: i16) -> Option<Row<'_>> {
+        let bounds = self.bounds();
+        (bounds.origin.y..bounds.origin.y + bounds.size.h)
+            .contains(&y)
+            .then(|| unsafe { Row::new(self.raw, y) })
+    }
+
+    /// The row at `y`, or `None` if it's outside the bounds.
+    pub fn row_mut(&mut self, y: i16) -> Option<RowMut<'_>> {
+        let bounds = self.bounds();
+        (bounds.origin.y..bounds.origin.y + bounds.size.h)
+            .contains(&y)
+            .then(|| unsafe { RowMut::new(self.raw, y) })
+    }
+
+    /// Each row within the bounds, from top to bottom.
+    pub fn rows(&self) -> Rows<'_> {
+        unsafe { Rows::new(self.raw) }
+    }
+
+    /// Each row within the bounds, from top to bottom.
+    pub fn rows_mut(&mut self) -> RowsMut<'_> {
+        unsafe { RowsMut::new(self.raw) }
+    }
+
+    /// The raw value of the pixel at `point`: a palette index, a single bit
+    /// for [`BitmapFormat::OneBit`], or a packed color for 8-bit formats.
+    /// Returns `None` if the pixel isn't within the bounds.
+    pub fn pixel(&self, point: Point) -> Option<u8> {
+        self.row(point.y)?.get(point.x)
+    }
+
+    /// The color of the pixel at `point`, looked up in the palette if the
+    /// bitmap has one.
+    pub fn pixel_color(&self, point: Point) -> Option<Color> {
+        self.row(point.y)?.color(point.x)
+    }
+
+    /// Set the raw value of the pixel at `point`, as returned by
+    /// [`pixel`](Bitmap::pixel).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pixel isn't within the bounds.
+    pub fn set_pixel(&mut self, point: Point, value: u8) {
+        self.row_mut(point.y)
+            .expect("Pixel out of bounds")
+            .set(point.x, value);
+    }
+}
+
+impl Drop for Bitmap {
+    fn drop(&mut self) {
+        unsafe { gbitmap_destroy(self.raw) }
+    }
+}
+
+/// A bitmap sharing the pixels of another, created with
+/// [`Bitmap::sub_bitmap`].
+pub struct SubBitmap<'a> {
+    bitmap: Bitmap,
+    _base: PhantomData<&'a Bitmap>,
+}
+
+impl Deref for SubBitmap<'_> {
+    type Target = Bitmap;
+
+    fn deref(&self) -> &Bitmap {
+        &self.bitmap
+    }
+}
+
+/// # Safety
+///
+/// `raw` must be a valid bitmap that outlives the lifetime `'a`, whose palette
+/// isn't otherwise borrowed.
+unsafe fn palette<'a>(raw: *mut GBitmap) -> Option<&'a mut [Color]> {
+    let size = BitmapFormat::from_raw(unsafe { gbitmap_get_format(raw) }).palette_size();
+    let palette = unsafe { gbitmap_get_palette(raw) };
+    (size > 0 && !palette.is_null())
+        .then(|| unsafe { slice::from_raw_parts_mut(palette as *mut Color, size) })
+}
+
+/// Where a row's pixels are, and which of them are within the bitmap.
+struct RowLayout {
+    format: BitmapFormat,
+    // Points to column 0, which may be outside the bitmap for round rows.
+    data: *mut u8,
+    min_x: i16,
+    max_x: i16,
+}
+
+impl RowLayout {
+    /// # Safety
+    ///
+    /// `raw` must be a valid bitmap, and `y` must be within its bounds.
+    unsafe fn new(raw: *mut GBitmap, y: i16) -> RowLayout {
+        let format = BitmapFormat::from_raw(unsafe { gbitmap_get_format(raw) });
+        #[cfg(pebble_sdk_platform = "chalk")]
+        if format == BitmapFormat::EightBitCircular {
+            let info = unsafe { pebblesdk_sys::gbitmap_get_data_row_info(raw, y as u16) };
+            return RowLayout {
+                format,
+                data: info.data,
+                min_x: info.min_x,
+                max_x: info.max_x,
+            };
+        }
+        let bounds: Rect = unsafe { gbitmap_get_bounds(raw) }.into();
+        let bytes_per_row = unsafe { gbitmap_get_bytes_per_row(raw) } as usize;
+        RowLayout {
+            format,
+            data: unsafe { gbitmap_get_data(raw).add(y as usize * bytes_per_row) },
+            min_x: bounds.origin.x,
+            max_x: bounds.origin.x + bounds.size.w - 1,
+        }
+    }
+
+    /// The bytes holding the row's pixels, and the index of the first of them
+    /// counting from column 0.
+    fn bytes(&self) -> (*mut u8, usize, usize) {
+        let bits = self.format.bits_per_pixel() as usize;
+        let start = self.min_x as usize * bits / 8;
+        let len = if self.max_x < self.min_x {
+            0
+        } else {
+            ((self.max_x as usize + 1) * bits).div_ceil(8) - start
+        };
+        (unsafe { self.data.add(start) }, start, len)
+    }
+
+    /// The byte index, relative to `start`, and the bit shift of pixel `x`.
+    fn locate(&self, x: i16, start: usize) -> Option<(usize, u32)> {
+        if x < self.min_x || x > self.max_x {
+            return None;
+        }
+        let bits = self.format.bits_per_pixel() as usize;
+        let bit = x as usize * bits;
+        let shift = match self.format {
+            // The leftmost pixel is in the least significant bit.
+            BitmapFormat::OneBit => bit % 8,
+            // The leftmost pixel is in the most significant bits.
+            _ => 8 - bits - bit % 8,
+        };
+        Some((bit / 8 - start, shift as u32))
+    }
+}
+
+fn mask(format: BitmapFormat) -> u8 {
+    (((1u16 << format.bits_per_pixel()) - 1) & 0xff) as u8
+}
+
+/// A row of pixels in a [`Bitmap`].
+///
+/// Pixels are addressed by their column in the bitmap's data, which for a
+/// sub-bitmap starts at the left edge of the base bitmap.
+pub struct Row<'a> {
+    layout: RowLayout,
+    y: i16,
+    data: &'a [u8],
+    start: usize,
+    palette: Option<&'a [Color]>,
+}
+
+impl<'a> Row<'a> {
+    /// # Safety
+    ///
+    /// `raw` must be a valid bitmap that outlives `'a` and isn't mutably
+    /// borrowed, and `y` must be within its bounds.
+    unsafe fn new(raw: *mut GBitmap, y: i16) -> Row<'a> {
+        let layout = unsafe { RowLayout::new(raw, y) };
+        let (data, start, len) = layout.bytes();
+        Row {
+            layout,
+            y,
+            data: unsafe { slice::from_raw_parts(data, len) },
+            start,
+            palette: unsafe { palette(raw) }.map(|palette| &*palette),
+        }
+    }
+
+    /// The row's position from the top of the bitmap's data.
+    pub fn y(&self) -> i16 {
+        self.y
+    }
+
+    /// The first column within the bitmap.
+    pub fn min_x(&self) -> i16 {
+        self.layout.min_x
+    }
+
+    /// The last column within the bitmap.
+    pub fn max_x(&self) -> i16 {
+        self.layout.max_x
+    }
+
+    /// The bytes holding the row's pixels.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// The raw value of the pixel in column `x`, or `None` if it's outside
+    /// the bitmap. See [`Bitmap::pixel`].
+    pub fn get(&self, x: i16) -> Option<u8> {
+        let (index, shift) = self.layout.locate(x, self.start)?;
+        Some((self.data[index] >> shift) & mask(self.layout.format))
+    }
+
+    /// The color of the pixel in column `x`, looked up in the palette if the
+    /// bitmap has one.
+    pub fn color(&self, x: i16) -> Option<Color> {
+        let value = self.get(x)?;
+        match self.layout.format {
+            BitmapFormat::OneBit if value == 0 => Some(Color::BLACK),
+            BitmapFormat::OneBit => Some(Color::WHITE),
+            BitmapFormat::EightBit | BitmapFormat::EightBitCircular => {
+                Some(Color::from_argb8(value))
+            }
+            _ => self.palette?.get(value as usize).copied(),
+        }
+    }
+
+    /// The column and raw value of each pixel within the bitmap, from left to
+    /// right.
+    pub fn pixels(&self) -> impl Iterator<Item = (i16, u8)> + '_ {
+        (self.min_x()..=self.max_x()).filter_map(|x| Some((x, self.get(x)?)))
+    }
+}
+
+/// A mutable row of pixels in a [`Bitmap`]. See [`Row`].
+pub struct RowMut<'a> {
+    layout: RowLayout,
+    y: i16,
+    data: &'a mut [u8],
+    start: usize,
+}
+
+impl<'a> RowMut<'a> {
+    /// # Safety
+    ///
+    /// `raw` must be a valid bitmap that outlives `'a`, `y` must be within
+    /// its bounds, and the row mustn't be otherwise borrowed.
+    unsafe fn new(raw: *mut GBitmap, y: i16) -> RowMut<'a> {
+        let layout = unsafe { RowLayout::new(raw, y) };
+        let (data, start, len) = layout.bytes();
+        RowMut {
+            layout,
+            y,
+            data: unsafe { slice::from_raw_parts_mut(data, len) },
+            start,
+        }
+    }
+
+    pub fn y(&self) -> i16 {
+        self.y
+    }
+
+    pub fn min_x(&self) -> i16 {
+        self.layout.min_x
+    }
+
+    pub fn max_x(&self) -> i16 {
+        self.layout.max_x
+    }
+
+    pub fn bytes_mut(&mut self) -> &mut [u8] {
+        &mut *self.data
+    }
+
+    pub fn get(&self, x: i16) -> Option<u8> {
+        let (index, shift) = self.layout.locate(x, self.start)?;
+        Some((self.data[index] >> shift) & mask(self.layout.format))
+    }
+
+    /// Set the raw value of the pixel in column `x`. Bits of `value` beyond
+    /// the format's bits per pixel are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` is outside the bitmap.
+    pub fn set(&mut self, x: i16, value: u8) {
+        let (index, shift) = self
+            .layout
+            .locate(x, self.start)
+            .expect("Pixel out of bounds");
+        let mask = mask(self.layout.format) << shift;
+        self.data[index] = (self.data[index] & !mask) | ((value << shift) & mask);
+    }
+}
+
+/// An iterator over the rows of a [`Bitmap`], created by [`Bitmap::rows`].
+pub struct Rows<'a> {
+    raw: *mut GBitmap,
+    y: i16,
+    end: i16,
+    _bitmap: PhantomData<&'a Bitmap>,
+}
+
+impl Rows<'_> {
+    unsafe fn new(raw: *mut GBitmap) -> Self {
+        let bounds: Rect = unsafe { gbitmap_get_bounds(raw) }.into();
+        Rows {
+            raw,
+            y: bounds.origin.y,
+            end: bounds.origin.y + bounds.size.h,
+            _bitmap: PhantomData,
+        }
+    }
+}
+
+impl<'a> Iterator for Rows<'a> {
+    type Item = Row<'a>;
+
+    fn next(&mut self) -> Option<Row<'a>> {
+        if self.y >= self.end {
+            return None;
+        }
+        let row = unsafe { Row::new(self.raw, self.y) };
+        self.y += 1;
+        Some(row)
+    }
+}
+
+/// An iterator over the mutable rows of a [`Bitmap`], created by
+/// [`Bitmap::rows_mut`].
+pub struct RowsMut<'a> {
+    raw: *mut GBitmap,
+    y: i16,
+    end: i16,
+    _bitmap: PhantomData<&'a mut Bitmap>,
+}
+
+impl RowsMut<'_> {
+    unsafe fn new(raw: *mut GBitmap) -> Self {
+        let bounds: Rect = unsafe { gbitmap_get_bounds(raw) }.into();
+        RowsMut {
+            raw,
+            y: bounds.origin.y,
+            end: bounds.origin.y + bounds.size.h,
+            _bitmap: PhantomData,
+        }
+    }
+}
+
+impl<'a> Iterator for RowsMut<'a> {
+    type Item = RowMut<'a>;
+
+    fn next(&mut self) -> Option<RowMut<'a>> {
+        if self.y >= self.end {
+            return None;
+        }
+        // Each row is yielded once, and rows don't share bytes.
+        let row = unsafe { RowMut::new(self.raw, self.y) };
+        self.y += 1;
+        Some(row)
+    }
+}
@@ -0,0 +1,369 @@
+//! Vector images and animations in the Pebble Draw Command (PDC) format.
+
+use pebblesdk_sys::{
+    gdraw_command_draw, gdraw_command_frame_draw, gdraw_command_frame_get_command_list,
+    gdraw_command_frame_get_duration, gdraw_command_frame_set_duration,
+    gdraw_command_get_fill_color, gdraw_command_get_hidden, gdraw_command_get_num_points,
+    gdraw_command_get_path_open, gdraw_command_get_point, gdraw_command_get_radius,
+    gdraw_command_get_stroke_color, gdraw_command_get_stroke_width, gdraw_command_get_type,
+    gdraw_command_image_clone, gdraw_command_image_create_with_resource,
+    gdraw_command_image_destroy, gdraw_command_image_draw, gdraw_command_image_get_bounds_size,
+    gdraw_command_image_get_command_list, gdraw_command_image_set_bounds_size,
+    gdraw_command_list_draw, gdraw_command_list_get_command, gdraw_command_list_get_num_commands,
+    gdraw_command_sequence_clone, gdraw_command_sequence_create_with_resource,
+    gdraw_command_sequence_destroy, gdraw_command_sequence_get_bounds_size,
+    gdraw_command_sequence_get_frame_by_elapsed, gdraw_command_sequence_get_frame_by_index,
+    gdraw_command_sequence_get_num_frames, gdraw_command_sequence_get_play_count,
+    gdraw_command_sequence_get_total_duration, gdraw_command_sequence_set_bounds_size,
+    gdraw_command_sequence_set_play_count, gdraw_command_set_fill_color, gdraw_command_set_hidden,
+    gdraw_command_set_path_open, gdraw_command_set_point, gdraw_command_set_radius,
+    gdraw_command_set_stroke_color, gdraw_command_set_stroke_width, GDrawCommand,
+    GDrawCommandFrame, GDrawCommandImage, GDrawCommandList, GDrawCommandSequence,
+    GDrawCommandTypeCircle, GDrawCommandTypePath, GDrawCommandTypePrecisePath, PLAY_COUNT_INFINITE,
+};
+
+use crate::graphics::{Color, GraphicsContext, Point, Size};
+
+/// Play a [`DrawCommandSequence`] forever, with
+/// [`DrawCommandSequence::set_play_count`].
+pub const INFINITE: u32 = PLAY_COUNT_INFINITE;
+
+/// The kind of shape a [`DrawCommand`] draws.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawCommandType {
+    /// A path through points on the pixel grid.
+    Path,
+    /// A circle around a single point.
+    Circle,
+    /// A path through points with 1/8 pixel precision.
+    PrecisePath,
+}
+
+/// A single shape within a draw command image or frame.
+///
+/// Only ever handled by reference, borrowed from a [`DrawCommandList`].
+#[repr(transparent)]
+pub struct DrawCommand(GDrawCommand);
+
+impl DrawCommand {
+    /// Get the underlying PebbleOS draw command, for use with
+    /// [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut GDrawCommand {
+        self as *const DrawCommand as *mut GDrawCommand
+    }
+
+    /// The kind of shape, or `None` if the command is invalid.
+    #[allow(non_upper_case_globals)]
+    pub fn command_type(&self) -> Option<DrawCommandType> {
+        match unsafe { gdraw_command_get_type(self.as_ptr()) } {
+            GDrawCommandTypePath => Some(DrawCommandType::Path),
+            GDrawCommandTypeCircle => Some(DrawCommandType::Circle),
+            GDrawCommandTypePrecisePath => Some(DrawCommandType::PrecisePath),
+            _ => None,
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut GraphicsContext) {
+        unsafe { gdraw_command_draw(ctx.as_ptr(), self.as_ptr()) }
+    }
+
+    pub fn fill_color(&self) -> Color {
+        Color::from_raw(unsafe { gdraw_command_get_fill_color(self.as_ptr()) })
+    }
+
+    pub fn set_fill_color(&mut self, color: Color) {
+        unsafe { gdraw_command_set_fill_color(self.as_ptr(), color.raw()) }
+    }
+
+    pub fn stroke_color(&self) -> Color {
+        Color::from_raw(unsafe { gdraw_command_get_stroke_color(self.as_ptr()) })
+    }
+
+    pub fn set_stroke_color(&mut self, color: Color) {
+        unsafe { gdraw_command_set_stroke_color(self.as_ptr(), color.raw()) }
+    }
+
+    pub fn stroke_width(&self) -> u8 {
+        unsafe { gdraw_command_get_stroke_width(self.as_ptr()) }
+    }
+
+    pub fn set_stroke_width(&mut self, width: u8) {
+        unsafe { gdraw_command_set_stroke_width(self.as_ptr(), width) }
+    }
+
+    pub fn num_points(&self) -> u16 {
+        unsafe { gdraw_command_get_num_points(self.as_ptr()) }
+    }
+
+    /// The point at `index`, or `None` if it's out of range.
+    pub fn point(&self, index: u16) -> Option<Point> {
+        (index < self.num_points())
+            .then(|| unsafe { gdraw_command_get_point(self.as_ptr(), index) }.into())
+    }
+
+    /// Move the point at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn set_point(&mut self, index: u16, point: Point) {
+        assert!(index < self.num_points(), "Point index out of range");
+        unsafe { gdraw_command_set_point(self.as_ptr(), index, point.into()) }
+    }
+
+    /// The radius of a circle. Meaningless for paths.
+    pub fn radius(&self) -> u16 {
+        unsafe { gdraw_command_get_radius(self.as_ptr()) }
+    }
+
+    pub fn set_radius(&mut self, radius: u16) {
+        unsafe { gdraw_command_set_radius(self.as_ptr(), radius) }
+    }
+
+    /// True if a path isn't closed back to its first point. Meaningless for
+    /// circles.
+    pub fn is_path_open(&self) -> bool {
+        unsafe { gdraw_command_get_path_open(self.as_ptr()) }
+    }
+
+    pub fn set_path_open(&mut self, open: bool) {
+        unsafe { gdraw_command_set_path_open(self.as_ptr(), open) }
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        unsafe { gdraw_command_get_hidden(self.as_ptr()) }
+    }
+
+    pub fn set_hidden(&mut self, hidden: bool) {
+        unsafe { gdraw_command_set_hidden(self.as_ptr(), hidden) }
+    }
+}
+
+/// The shapes making up an image or frame, drawn in order.
+///
+/// Only ever handled by reference, borrowed from a [`DrawCommandImage`] or
+/// [`DrawCommandFrame`].
+#[repr(transparent)]
+pub struct DrawCommandList(GDrawCommandList);
+
+impl DrawCommandList {
+    unsafe fn from_ptr<'a>(raw: *mut GDrawCommandList) -> &'a mut DrawCommandList {
+        unsafe { &mut *(raw as *mut DrawCommandList) }
+    }
+
+    /// Get the underlying PebbleOS command list, for use with
+    /// [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut GDrawCommandList {
+        self as *const DrawCommandList as *mut GDrawCommandList
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { gdraw_command_list_get_num_commands(self.as_ptr()) as usize }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn command(&self, index: usize) -> Option<*mut DrawCommand> {
+        if index >= self.len() {
+            return None;
+        }
+        let command = unsafe { gdraw_command_list_get_command(self.as_ptr(), index as u16) };
+        (!command.is_null()).then_some(command as *mut DrawCommand)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&DrawCommand> {
+        self.command(index).map(|command| unsafe { &*command })
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut DrawCommand> {
+        self.command(index).map(|command| unsafe { &mut *command })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DrawCommand> {
+        (0..self.len()).filter_map(|index| self.get(index))
+    }
+
+    /// Call `f` with each command in turn, such as to recolor every shape.
+    pub fn for_each_mut<F: FnMut(&mut DrawCommand)>(&mut self, mut f: F) {
+        for index in 0..self.len() {
+            if let Some(command) = self.get_mut(index) {
+                f(command);
+            }
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut GraphicsContext) {
+        unsafe { gdraw_command_list_draw(ctx.as_ptr(), self.as_ptr()) }
+    }
+}
+
+/// An owned vector image, destroyed when dropped.
+pub struct DrawCommandImage {
+    raw: *mut GDrawCommandImage,
+}
+
+impl DrawCommandImage {
+    /// Load an image from a raw PDC resource. Returns `None` if the resource
+    /// isn't a PDC image or there isn't enough memory to load it.
+    pub fn with_resource(resource_id: u32) -> Option<DrawCommandImage> {
+        let raw = unsafe { gdraw_command_image_create_with_resource(resource_id) };
+        (!raw.is_null()).then_some(DrawCommandImage { raw })
+    }
+
+    /// Get the underlying PebbleOS image, for use with [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut GDrawCommandImage {
+        self.raw
+    }
+
+    /// Draw the image with its top left corner at `offset`.
+    pub fn draw(&self, ctx: &mut GraphicsContext, offset: Point) {
+        unsafe { gdraw_command_image_draw(ctx.as_ptr(), self.raw, offset.into()) }
+    }
+
+    pub fn bounds_size(&self) -> Size {
+        unsafe { gdraw_command_image_get_bounds_size(self.raw) }.into()
+    }
+
+    pub fn set_bounds_size(&mut self, size: Size) {
+        unsafe { gdraw_command_image_set_bounds_size(self.raw, size.into()) }
+    }
+
+    pub fn commands(&self) -> &DrawCommandList {
+        unsafe { DrawCommandList::from_ptr(gdraw_command_image_get_command_list(self.raw)) }
+    }
+
+    pub fn commands_mut(&mut self) -> &mut DrawCommandList {
+        unsafe { DrawCommandList::from_ptr(gdraw_command_image_get_command_list(self.raw)) }
+    }
+
+    /// Copy the image, so the copy can be changed independently. Returns
+    /// `None` if there isn't enough memory.
+    pub fn try_clone(&self) -> Option<DrawCommandImage> {
+        let raw = unsafe { gdraw_command_image_clone(self.raw) };
+        (!raw.is_null()).then_some(DrawCommandImage { raw })
+    }
+}
+
+impl Drop for DrawCommandImage {
+    fn drop(&mut self) {
+        unsafe { gdraw_command_image_destroy(self.raw) }
+    }
+}
+
+/// One frame of a [`DrawCommandSequence`].
+///
+/// Only ever handled by reference, borrowed from its sequence.
+#[repr(transparent)]
+pub struct DrawCommandFrame(GDrawCommandFrame);
+
+impl DrawCommandFrame {
+    /// Get the underlying PebbleOS frame, for use with [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut GDrawCommandFrame {
+        self as *const DrawCommandFrame as *mut GDrawCommandFrame
+    }
+
+    /// How long the frame is shown for, in milliseconds.
+    pub fn duration(&self) -> u32 {
+        unsafe { gdraw_command_frame_get_duration(self.as_ptr()) }
+    }
+
+    pub fn set_duration(&mut self, duration_ms: u32) {
+        unsafe { gdraw_command_frame_set_duration(self.as_ptr(), duration_ms) }
+    }
+
+    pub fn commands(&self) -> &DrawCommandList {
+        unsafe { DrawCommandList::from_ptr(gdraw_command_frame_get_command_list(self.as_ptr())) }
+    }
+
+    pub fn commands_mut(&mut self) -> &mut DrawCommandList {
+        unsafe { DrawCommandList::from_ptr(gdraw_command_frame_get_command_list(self.as_ptr())) }
+    }
+}
+
+/// An owned vector animation, destroyed when dropped.
+pub struct DrawCommandSequence {
+    raw: *mut GDrawCommandSequence,
+}
+
+impl DrawCommandSequence {
+    /// Load a sequence from a raw PDC resource. Returns `None` if the
+    /// resource isn't a PDC sequence or there isn't enough memory to load it.
+    pub fn with_resource(resource_id: u32) -> Option<DrawCommandSequence> {
+        let raw = unsafe { gdraw_command_sequence_create_with_resource(resource_id) };
+        (!raw.is_null()).then_some(DrawCommandSequence { raw })
+    }
+
+    /// Get the underlying PebbleOS sequence, for use with [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut GDrawCommandSequence {
+        self.raw
+    }
+
+    fn frame_ptr(&self, frame: *mut GDrawCommandFrame) -> Option<*mut DrawCommandFrame> {
+        (!frame.is_null()).then_some(frame as *mut DrawCommandFrame)
+    }
+
+    /// The frame to show `elapsed_ms` milliseconds after the sequence
+    /// started, taking the play count into account. Once the sequence has
+    /// finished, this is the last frame.
+    pub fn frame_by_elapsed(&self, elapsed_ms: u32) -> Option<&DrawCommandFrame> {
+        let frame = unsafe { gdraw_command_sequence_get_frame_by_elapsed(self.raw, elapsed_ms) };
+        self.frame_ptr(frame).map(|frame| unsafe { &*frame })
+    }
+
+    pub fn frame(&self, index: u32) -> Option<&DrawCommandFrame> {
+        let frame = unsafe { gdraw_command_sequence_get_frame_by_index(self.raw, index) };
+        self.frame_ptr(frame).map(|frame| unsafe { &*frame })
+    }
+
+    pub fn frame_mut(&mut self, index: u32) -> Option<&mut DrawCommandFrame> {
+        let frame = unsafe { gdraw_command_sequence_get_frame_by_index(self.raw, index) };
+        self.frame_ptr(frame).map(|frame| unsafe { &mut *frame })
+    }
+
+    pub fn num_frames(&self) -> u32 {
+        unsafe { gdraw_command_sequence_get_num_frames(self.raw) }
+    }
+
+    /// Draw `frame`, which must belong to this sequence, with its top left
+    /// corner at `offset`.
+    pub fn draw_frame(&self, ctx: &mut GraphicsContext, frame: &DrawCommandFrame, offset: Point) {
+        unsafe { gdraw_command_frame_draw(ctx.as_ptr(), self.raw, frame.as_ptr(), offset.into()) }
+    }
+
+    pub fn bounds_size(&self) -> Size {
+        unsafe { gdraw_command_sequence_get_bounds_size(self.raw) }.into()
+    }
+
+    pub fn set_bounds_size(&mut self, size: Size) {
+        unsafe { gdraw_command_sequence_set_bounds_size(self.raw, size.into()) }
+    }
+
+    /// How many times the sequence plays, or [`INFINITE`].
+    pub fn play_count(&self) -> u32 {
+        unsafe { gdraw_command_sequence_get_play_count(self.raw) }
+    }
+
+    pub fn set_play_count(&mut self, play_count: u32) {
+        unsafe { gdraw_command_sequence_set_play_count(self.raw, play_count) }
+    }
+
+    /// The length of one play of the sequence, in milliseconds.
+    pub fn total_duration(&self) -> u32 {
+        unsafe { gdraw_command_sequence_get_total_duration(self.raw) }
+    }
+
+    /// Copy the sequence, so the copy can be changed independently. Returns
+    /// `None` if there isn't enough memory.
+    pub fn try_clone(&self) -> Option<DrawCommandSequence> {
+        let raw = unsafe { gdraw_command_sequence_clone(self.raw) };
+        (!raw.is_null()).then_some(DrawCommandSequence { raw })
+    }
+}
+
+impl Drop for DrawCommandSequence {
+    fn drop(&mut self) {
+        unsafe { gdraw_command_sequence_destroy(self.raw) }
+    }
+}
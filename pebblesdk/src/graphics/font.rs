@@ -0,0 +1,225 @@
+//! Built-in fonts, and fonts loaded from resources.
+
+use core::ffi::c_char;
+use core::marker::PhantomData;
+
+use rust_alloc::ffi::CString;
+
+use pebblesdk_sys::{
+    fonts_get_system_font, fonts_load_custom_font, fonts_unload_custom_font,
+    graphics_text_layout_get_content_size, resource_get_handle, GFont,
+    FONT_KEY_BITHAM_18_LIGHT_SUBSET, FONT_KEY_BITHAM_30_BLACK, FONT_KEY_BITHAM_34_LIGHT_SUBSET,
+    FONT_KEY_BITHAM_34_MEDIUM_NUMBERS, FONT_KEY_BITHAM_42_BOLD, FONT_KEY_BITHAM_42_LIGHT,
+    FONT_KEY_BITHAM_42_MEDIUM_NUMBERS, FONT_KEY_DROID_SERIF_28_BOLD, FONT_KEY_FONT_FALLBACK,
+    FONT_KEY_GOTHIC_14, FONT_KEY_GOTHIC_14_BOLD, FONT_KEY_GOTHIC_18, FONT_KEY_GOTHIC_18_BOLD,
+    FONT_KEY_GOTHIC_24, FONT_KEY_GOTHIC_24_BOLD, FONT_KEY_GOTHIC_28, FONT_KEY_GOTHIC_28_BOLD,
+    FONT_KEY_ROBOTO_BOLD_SUBSET_49, FONT_KEY_ROBOTO_CONDENSED_21,
+};
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+use pebblesdk_sys::{
+    graphics_text_layout_get_content_size_with_attributes, FONT_KEY_LECO_20_BOLD_NUMBERS,
+    FONT_KEY_LECO_26_BOLD_NUMBERS_AM_PM, FONT_KEY_LECO_28_LIGHT_NUMBERS,
+    FONT_KEY_LECO_32_BOLD_NUMBERS, FONT_KEY_LECO_36_BOLD_NUMBERS, FONT_KEY_LECO_38_BOLD_NUMBERS,
+    FONT_KEY_LECO_42_NUMBERS,
+};
+
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+use crate::graphics::text::TextAttributes;
+use crate::graphics::text::{TextAlignment, TextOverflowMode};
+use crate::graphics::{Rect, Size};
+
+/// A font to draw text with, borrowed from PebbleOS or a [`CustomFont`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Font<'a> {
+    raw: GFont,
+    _font: PhantomData<&'a CustomFont>,
+}
+
+impl Font<'_> {
+    /// Get the underlying PebbleOS font, for use with [`crate::sys`].
+    pub fn as_ptr(&self) -> GFont {
+        self.raw
+    }
+
+    /// The size `text` takes up when laid out within `bounds`, such as to
+    /// size a text layer to fit it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` contains a nul byte.
+    pub fn content_size(
+        &self,
+        text: &str,
+        bounds: Rect,
+        overflow: TextOverflowMode,
+        alignment: TextAlignment,
+    ) -> Size {
+        let text = CString::new(text).expect("Text contains a nul byte");
+        unsafe {
+            graphics_text_layout_get_content_size(
+                text.as_ptr(),
+                self.raw,
+                bounds.into(),
+                overflow.raw(),
+                alignment.raw(),
+            )
+        }
+        .into()
+    }
+
+    /// Like [`Font::content_size`], but laid out with `attributes`, such as
+    /// to measure text flowing around the edge of a round screen.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` contains a nul byte.
+    #[cfg(not(pebble_sdk_platform = "aplite"))]
+    pub fn content_size_with_attributes(
+        &self,
+        text: &str,
+        bounds: Rect,
+        overflow: TextOverflowMode,
+        alignment: TextAlignment,
+        attributes: &TextAttributes,
+    ) -> Size {
+        let text = CString::new(text).expect("Text contains a nul byte");
+        unsafe {
+            graphics_text_layout_get_content_size_with_attributes(
+                text.as_ptr(),
+                self.raw,
+                bounds.into(),
+                overflow.raw(),
+                alignment.raw(),
+                attributes.as_ptr(),
+            )
+        }
+        .into()
+    }
+}
+
+/// A font built into PebbleOS.
+///
+/// The `Subset` and `Numbers` fonts only include some characters, such as
+/// digits and a colon for showing the time.
+///
+/// ```ignore
+/// let font = SystemFont::Gothic28Bold.font();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SystemFont {
+    /// The font PebbleOS uses when a font can't be found.
+    Fallback,
+    Gothic14,
+    Gothic14Bold,
+    Gothic18,
+    Gothic18Bold,
+    Gothic24,
+    Gothic24Bold,
+    Gothic28,
+    Gothic28Bold,
+    Bitham18LightSubset,
+    Bitham30Black,
+    Bitham34LightSubset,
+    Bitham34MediumNumbers,
+    Bitham42Bold,
+    Bitham42Light,
+    Bitham42MediumNumbers,
+    RobotoCondensed21,
+    RobotoBoldSubset49,
+    DroidSerif28Bold,
+    #[cfg(not(pebble_sdk_platform = "aplite"))]
+    Leco20BoldNumbers,
+    #[cfg(not(pebble_sdk_platform = "aplite"))]
+    Leco26BoldNumbersAmPm,
+    #[cfg(not(pebble_sdk_platform = "aplite"))]
+    Leco28LightNumbers,
+    #[cfg(not(pebble_sdk_platform = "aplite"))]
+    Leco32BoldNumbers,
+    #[cfg(not(pebble_sdk_platform = "aplite"))]
+    Leco36BoldNumbers,
+    #[cfg(not(pebble_sdk_platform = "aplite"))]
+    Leco38BoldNumbers,
+    #[cfg(not(pebble_sdk_platform = "aplite"))]
+    Leco42Numbers,
+}
+
+impl SystemFont {
+    /// The nul-terminated key PebbleOS looks the font up by.
+    fn key(self) -> &'static [u8] {
+        match self {
+            SystemFont::Fallback => FONT_KEY_FONT_FALLBACK,
+            SystemFont::Gothic14 => FONT_KEY_GOTHIC_14,
+            SystemFont::Gothic14Bold => FONT_KEY_GOTHIC_14_BOLD,
+            SystemFont::Gothic18 => FONT_KEY_GOTHIC_18,
+            SystemFont::Gothic18Bold => FONT_KEY_GOTHIC_18_BOLD,
+            SystemFont::Gothic24 => FONT_KEY_GOTHIC_24,
+            SystemFont::Gothic24Bold => FONT_KEY_GOTHIC_24_BOLD,
+            SystemFont::Gothic28 => FONT_KEY_GOTHIC_28,
+            SystemFont::Gothic28Bold => FONT_KEY_GOTHIC_28_BOLD,
+            SystemFont::Bitham18LightSubset => FONT_KEY_BITHAM_18_LIGHT_SUBSET,
+            SystemFont::Bitham30Black => FONT_KEY_BITHAM_30_BLACK,
+            SystemFont::Bitham34LightSubset => FONT_KEY_BITHAM_34_LIGHT_SUBSET,
+            SystemFont::Bitham34MediumNumbers => FONT_KEY_BITHAM_34_MEDIUM_NUMBERS,
+            SystemFont::Bitham42Bold => FONT_KEY_BITHAM_42_BOLD,
+            SystemFont::Bitham42Light => FONT_KEY_BITHAM_42_LIGHT,
+            SystemFont::Bitham42MediumNumbers => FONT_KEY_BITHAM_42_MEDIUM_NUMBERS,
+            SystemFont::RobotoCondensed21 => FONT_KEY_ROBOTO_CONDENSED_21,
+            SystemFont::RobotoBoldSubset49 => FONT_KEY_ROBOTO_BOLD_SUBSET_49,
+            SystemFont::DroidSerif28Bold => FONT_KEY_DROID_SERIF_28_BOLD,
+            #[cfg(not(pebble_sdk_platform = "aplite"))]
+            SystemFont::Leco20BoldNumbers => FONT_KEY_LECO_20_BOLD_NUMBERS,
+            #[cfg(not(pebble_sdk_platform = "aplite"))]
+            SystemFont::Leco26BoldNumbersAmPm => FONT_KEY_LECO_26_BOLD_NUMBERS_AM_PM,
+            #[cfg(not(pebble_sdk_platform = "aplite"))]
+            SystemFont::Leco28LightNumbers => FONT_KEY_LECO_28_LIGHT_NUMBERS,
+            #[cfg(not(pebble_sdk_platform = "aplite"))]
+            SystemFont::Leco32BoldNumbers => FONT_KEY_LECO_32_BOLD_NUMBERS,
+            #[cfg(not(pebble_sdk_platform = "aplite"))]
+            SystemFont::Leco36BoldNumbers => FONT_KEY_LECO_36_BOLD_NUMBERS,
+            #[cfg(not(pebble_sdk_platform = "aplite"))]
+            SystemFont::Leco38BoldNumbers => FONT_KEY_LECO_38_BOLD_NUMBERS,
+            #[cfg(not(pebble_sdk_platform = "aplite"))]
+            SystemFont::Leco42Numbers => FONT_KEY_LECO_42_NUMBERS,
+        }
+    }
+
+    pub fn font(self) -> Font<'static> {
+        let raw = unsafe { fonts_get_system_font(self.key().as_ptr() as *const c_char) };
+        Font {
+            raw,
+            _font: PhantomData,
+        }
+    }
+}
+
+/// An owned font loaded from a font resource, unloaded when dropped.
+///
+/// ```ignore
+/// let font = CustomFont::with_resource(RESOURCE_ID_FONT_DIGITS_40).unwrap();
+/// unsafe { text_layer_set_font(text_layer, font.font().as_ptr()) };
+/// ```
+pub struct CustomFont {
+    raw: GFont,
+}
+
+impl CustomFont {
+    /// Load a font from a font resource. Returns `None` if the resource
+    /// isn't a font or there isn't enough memory to load it.
+    pub fn with_resource(resource_id: u32) -> Option<CustomFont> {
+        let raw = unsafe { fonts_load_custom_font(resource_get_handle(resource_id)) };
+        (!raw.is_null()).then_some(CustomFont { raw })
+    }
+
+    pub fn font(&self) -> Font<'_> {
+        Font {
+            raw: self.raw,
+            _font: PhantomData,
+        }
+    }
+}
+
+impl Drop for CustomFont {
+    fn drop(&mut self) {
+        unsafe { fonts_unload_custom_font(self.raw) }
+    }
+}
@@ -0,0 +1,237 @@
+//! Direct access to the screen's pixels while drawing.
+
+use core::mem::ManuallyDrop;
+use core::ops::Deref;
+
+use rust_alloc::vec;
+use rust_alloc::vec::Vec;
+
+use pebblesdk_sys::{graphics_capture_frame_buffer, graphics_release_frame_buffer, GContext};
+
+use crate::graphics::bitmap::{RowMut, RowsMut};
+use crate::graphics::{Bitmap, BitmapFormat, Color, GraphicsContext, Point, Rect};
+
+/// The screen's pixels, captured from a [`GraphicsContext`] with
+/// [`GraphicsContext::with_framebuffer`].
+///
+/// Derefs to a [`Bitmap`] for reading pixels, and has the same methods for
+/// writing them. The bitmap itself belongs to PebbleOS, so it can't be
+/// borrowed mutably. On round displays each row only covers the visible part
+/// of the screen, which [`Bitmap::rows`] accounts for.
+///
+/// ```ignore
+/// ctx.with_framebuffer(|framebuffer| {
+///     framebuffer.invert(Rect::new(0, 0, 144, 84));
+///     framebuffer.blur(Rect::new(0, 84, 144, 84), 2);
+/// });
+/// ```
+pub struct Framebuffer {
+    ctx: *mut GContext,
+    // Owned by PebbleOS, so it is released rather than destroyed.
+    bitmap: ManuallyDrop<Bitmap>,
+}
+
+impl Framebuffer {
+    pub(crate) fn capture(ctx: &mut GraphicsContext) -> Option<Framebuffer> {
+        let ctx = ctx.as_ptr();
+        let raw = unsafe { graphics_capture_frame_buffer(ctx) };
+        Bitmap::from_raw(raw).map(|bitmap| Framebuffer {
+            ctx,
+            bitmap: ManuallyDrop::new(bitmap),
+        })
+    }
+
+    /// See [`Bitmap::data_mut`].
+    pub fn data_mut(&mut self) -> Option<&mut [u8]> {
+        self.bitmap.data_mut()
+    }
+
+    /// See [`Bitmap::palette_mut`].
+    pub fn palette_mut(&mut self) -> Option<&mut [Color]> {
+        self.bitmap.palette_mut()
+    }
+
+    /// See [`Bitmap::row_mut`].
+    pub fn row_mut(&mut self, y: i16) -> Option<RowMut<'_>> {
+        self.bitmap.row_mut(y)
+    }
+
+    /// See [`Bitmap::rows_mut`].
+    pub fn rows_mut(&mut self) -> RowsMut<'_> {
+        self.bitmap.rows_mut()
+    }
+
+    /// See [`Bitmap::set_pixel`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pixel isn't on screen.
+    pub fn set_pixel(&mut self, point: Point, value: u8) {
+        self.bitmap.set_pixel(point, value)
+    }
+
+    /// Invert the colors of every pixel within `rect`, leaving alpha alone.
+    pub fn invert(&mut self, rect: Rect) {
+        let format = self.format();
+        let mask = match format {
+            BitmapFormat::OneBit => 1,
+            _ => 0b00111111,
+        };
+        self.for_each_span(rect, |row, min_x, max_x| {
+            for x in min_x..=max_x {
+                let value = row.get(x).unwrap_or_default();
+                row.set(x, value ^ mask);
+            }
+        });
+    }
+
+    /// Cover every other pixel within `rect` with `color`, in a checkerboard
+    /// pattern, such as to dim what's behind a dialog.
+    pub fn dither(&mut self, rect: Rect, color: Color) {
+        let value = raw_value(self.format(), color);
+        self.for_each_span(rect, |row, min_x, max_x| {
+            let y = row.y();
+            for x in (min_x..=max_x).filter(|x| (x + y) % 2 == 0) {
+                row.set(x, value);
+            }
+        });
+    }
+
+    /// Blur the pixels within `rect` by averaging each with its neighbors up
+    /// to `radius` pixels away. Only pixels within `rect` are sampled.
+    pub fn blur(&mut self, rect: Rect, radius: u8) {
+        let Some(rect) = intersect(rect, self.bounds()) else {
+            return;
+        };
+        if radius == 0 {
+            return;
+        }
+        let format = self.format();
+        let radius = radius as i16;
+        let (left, right) = (rect.origin.x, rect.origin.x + rect.size.w - 1);
+        let (top, bottom) = (rect.origin.y, rect.origin.y + rect.size.h - 1);
+
+        // Blurred pixels mustn't feed into their neighbors, so sample from
+        // copies of the rows within `radius` of the one being blurred. Each
+        // copy is kept with the columns it covers, which on round displays
+        // may be narrower than `rect`.
+        let width = rect.size.w as usize;
+        let window = (2 * radius + 1).min(rect.size.h) as usize;
+        let mut source = vec![0u8; window * width];
+        let mut spans: Vec<Option<(i16, i16)>> = vec![None; window];
+        let slot = |y: i16| (y - top) as usize % window;
+
+        let mut copied = top - 1;
+        for y in top..=bottom {
+            while copied < (y + radius).min(bottom) {
+                copied += 1;
+                let copy = &mut source[slot(copied) * width..][..width];
+                spans[slot(copied)] = self.row(copied).and_then(|row| {
+                    let (min_x, max_x) = (row.min_x().max(left), row.max_x().min(right));
+                    for x in min_x..=max_x {
+                        copy[(x - left) as usize] = row.get(x).unwrap_or_default();
+                    }
+                    (min_x <= max_x).then_some((min_x, max_x))
+                });
+            }
+            let Some((min_x, max_x)) = spans[slot(y)] else {
+                continue;
+            };
+            let Some(mut row) = self.bitmap.row_mut(y) else {
+                continue;
+            };
+
+            let rows = (y - radius).max(top)..=(y + radius).min(bottom);
+            for x in min_x..=max_x {
+                let columns = (x - radius).max(left)..=(x + radius).min(right);
+                let mut total = [0u32; 4];
+                let mut count = 0;
+                for sample_y in rows.clone() {
+                    let Some((span_min, span_max)) = spans[slot(sample_y)] else {
+                        continue;
+                    };
+                    let copy = &source[slot(sample_y) * width..][..width];
+                    for sample_x in columns.clone() {
+                        if sample_x < span_min || sample_x > span_max {
+                            continue;
+                        }
+                        let value = copy[(sample_x - left) as usize];
+                        for (channel, total) in total.iter_mut().enumerate() {
+                            *total += channel_of(format, value, channel) as u32;
+                        }
+                        count += 1;
+                    }
+                }
+                let average = total.map(|total| ((total + count / 2) / count) as u8);
+                row.set(x, from_channels(format, average));
+            }
+        }
+    }
+
+    /// Call `f` with each row overlapping `rect`, and the first and last
+    /// columns of it within both `rect` and the row.
+    fn for_each_span<F: FnMut(&mut RowMut<'_>, i16, i16)>(&mut self, rect: Rect, mut f: F) {
+        let Some(rect) = intersect(rect, self.bounds()) else {
+            return;
+        };
+        for y in rect.origin.y..rect.origin.y + rect.size.h {
+            let Some(mut row) = self.bitmap.row_mut(y) else {
+                continue;
+            };
+            let min_x = row.min_x().max(rect.origin.x);
+            let max_x = row.max_x().min(rect.origin.x + rect.size.w - 1);
+            if min_x <= max_x {
+                f(&mut row, min_x, max_x);
+            }
+        }
+    }
+}
+
+impl Deref for Framebuffer {
+    type Target = Bitmap;
+
+    fn deref(&self) -> &Bitmap {
+        &self.bitmap
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe { graphics_release_frame_buffer(self.ctx, self.bitmap.as_ptr()) };
+    }
+}
+
+fn intersect(a: Rect, b: Rect) -> Option<Rect> {
+    let left = a.origin.x.max(b.origin.x);
+    let top = a.origin.y.max(b.origin.y);
+    let right = (a.origin.x + a.size.w).min(b.origin.x + b.size.w);
+    let bottom = (a.origin.y + a.size.h).min(b.origin.y + b.size.h);
+    (left < right && top < bottom).then(|| Rect::new(left, top, right - left, bottom - top))
+}
+
+/// The raw pixel value showing `color` in a framebuffer of `format`.
+fn raw_value(format: BitmapFormat, color: Color) -> u8 {
+    match format {
+        // Anything but black shows as white.
+        BitmapFormat::OneBit => (color.argb8() & 0b00111111 != 0) as u8,
+        _ => color.argb8(),
+    }
+}
+
+/// Channel `channel` of a raw pixel, scaled to 0-255 for one-bit pixels and
+/// 0-3 for colors.
+fn channel_of(format: BitmapFormat, value: u8, channel: usize) -> u8 {
+    match format {
+        BitmapFormat::OneBit => value * 255,
+        _ => (value >> (6 - 2 * channel)) & 3,
+    }
+}
+
+fn from_channels(format: BitmapFormat, channels: [u8; 4]) -> u8 {
+    match format {
+        BitmapFormat::OneBit => (channels[0] >= 128) as u8,
+        _ => channels
+            .iter()
+            .fold(0, |value, channel| (value << 2) | (channel & 3)),
+    }
+}
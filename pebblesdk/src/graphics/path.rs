@@ -0,0 +1,105 @@
+//! Polygons and polylines that can be rotated and moved as a whole.
+
+use rust_alloc::boxed::Box;
+
+use pebblesdk_sys::{
+    gpath_create, gpath_destroy, gpath_move_to, gpath_rotate_to, GPath, GPathInfo, GPoint,
+};
+
+use crate::graphics::Point;
+use crate::trig::Angle;
+
+/// An owned PebbleOS path, drawn with [`GraphicsContext::draw_path_filled`]
+/// and related methods.
+///
+/// Points are given relative to the path's origin, which is placed at its
+/// offset after rotating around it. This suits clock hands, drawn pointing
+/// up from their pivot:
+///
+/// ```ignore
+/// let mut hand = Path::new(&[
+///     Point::new(-4, 8),
+///     Point::new(4, 8),
+///     Point::new(0, -60),
+/// ]);
+/// hand.move_to(bounds.center());
+/// hand.rotate_to(Angle::from_degrees(minute * 6));
+/// ctx.draw_path_filled(&hand);
+/// ```
+///
+/// [`GraphicsContext::draw_path_filled`]: crate::graphics::GraphicsContext::draw_path_filled
+pub struct Path {
+    raw: *mut GPath,
+    // PebbleOS points into this, so it must not be resized.
+    points: Box<[GPoint]>,
+}
+
+impl Path {
+    /// Create a path through `points`, copying them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory.
+    pub fn new(points: &[Point]) -> Path {
+        let mut points: Box<[GPoint]> = points.iter().map(|&point| point.into()).collect();
+        let info = GPathInfo {
+            num_points: points.len() as u32,
+            points: points.as_mut_ptr(),
+        };
+        let raw = unsafe { gpath_create(&info) };
+        assert!(!raw.is_null(), "Failed to create path");
+        Path { raw, points }
+    }
+
+    /// Get the underlying PebbleOS path, for use with [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut GPath {
+        self.raw
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The points of the path, before rotating and moving it.
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.points.iter().map(|&point| point.into())
+    }
+
+    /// Move the point at `index`, relative to the path's origin.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn set_point(&mut self, index: usize, point: Point) {
+        self.points[index] = point.into();
+    }
+
+    pub fn rotation(&self) -> Angle {
+        Angle::from_raw(unsafe { (*self.raw).rotation })
+    }
+
+    /// Set the rotation around the path's origin, replacing any previous
+    /// rotation.
+    pub fn rotate_to(&mut self, angle: Angle) {
+        unsafe { gpath_rotate_to(self.raw, angle.raw()) }
+    }
+
+    pub fn offset(&self) -> Point {
+        unsafe { (*self.raw).offset }.into()
+    }
+
+    /// Place the path's origin at `offset`, replacing any previous offset.
+    pub fn move_to(&mut self, offset: Point) {
+        unsafe { gpath_move_to(self.raw, offset.into()) }
+    }
+}
+
+impl Drop for Path {
+    fn drop(&mut self) {
+        unsafe { gpath_destroy(self.raw) }
+    }
+}
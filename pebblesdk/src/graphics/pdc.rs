@@ -0,0 +1,435 @@
+//! Reading and writing Pebble Draw Command (PDC) files.
+//!
+//! Unlike the `draw_command` module, nothing here calls into PebbleOS, so it
+//! can be used on the host, such as to generate vector art or check resources
+//! before bundling them. The `pebblesdk-build` crate builds this module for
+//! use from build scripts.
+//!
+//! Images are stored as `PDCI` files and sequences as `PDCS` files. All
+//! numbers are little-endian.
+
+use core::fmt;
+
+use rust_alloc::vec::Vec;
+
+use crate::graphics::{Color, Point, Size};
+
+const IMAGE_MAGIC: &[u8; 4] = b"PDCI";
+const SEQUENCE_MAGIC: &[u8; 4] = b"PDCS";
+const VERSION: u8 = 1;
+
+const TYPE_PATH: u8 = 1;
+const TYPE_CIRCLE: u8 = 2;
+const TYPE_PRECISE_PATH: u8 = 3;
+
+const FLAG_HIDDEN: u8 = 1 << 0;
+const PATH_OPEN: u16 = 1 << 0;
+
+/// Why a PDC file couldn't be parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The file doesn't start with `PDCI` or `PDCS`, whichever was expected.
+    BadMagic,
+    /// The file is in a version of the format other than 1.
+    UnsupportedVersion(u8),
+    /// A command has a type other than path, circle, or precise path.
+    UnknownCommandType(u8),
+    /// The file ended partway through.
+    Truncated,
+    /// The size in the header doesn't match the contents.
+    SizeMismatch,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::BadMagic => f.write_str("not a PDC file of the expected kind"),
+            ParseError::UnsupportedVersion(version) => {
+                write!(f, "unsupported PDC version {version}")
+            }
+            ParseError::UnknownCommandType(kind) => write!(f, "unknown draw command type {kind}"),
+            ParseError::Truncated => f.write_str("PDC file is truncated"),
+            ParseError::SizeMismatch => f.write_str("PDC header size doesn't match its contents"),
+        }
+    }
+}
+
+/// The kind of shape a [`Command`] draws, with its shape-specific setting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    /// A path through points on the pixel grid, closed back to its first
+    /// point unless `open`.
+    Path { open: bool },
+    /// A circle of `radius` around a single point.
+    Circle { radius: u16 },
+    /// A path through points given in eighths of a pixel.
+    PrecisePath { open: bool },
+}
+
+/// A single shape within an image or frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Command {
+    pub shape: Shape,
+    pub hidden: bool,
+    pub stroke_color: Color,
+    pub stroke_width: u8,
+    pub fill_color: Color,
+    /// The points of the shape. For [`Shape::PrecisePath`] these are in
+    /// eighths of a pixel.
+    pub points: Vec<Point>,
+}
+
+/// A vector image, as stored in a `PDCI` file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub view_box: Size,
+    pub commands: Vec<Command>,
+}
+
+/// A single frame of a [`Sequence`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// How long the frame is shown, in milliseconds.
+    pub duration: u16,
+    pub commands: Vec<Command>,
+}
+
+/// A vector animation, as stored in a `PDCS` file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sequence {
+    pub view_box: Size,
+    /// How many times the sequence plays.
+    pub play_count: u16,
+    pub frames: Vec<Frame>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ParseError> {
+        if self.data.len() < len {
+            return Err(ParseError::Truncated);
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, ParseError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ParseError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, ParseError> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, ParseError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Check the magic and size of a file, returning a reader over its body.
+    fn body(data: &'a [u8], magic: &[u8; 4]) -> Result<Reader<'a>, ParseError> {
+        let mut reader = Reader { data };
+        if reader.take(4).map_err(|_| ParseError::BadMagic)? != magic {
+            return Err(ParseError::BadMagic);
+        }
+        let size = reader.u32()? as usize;
+        let body = Reader {
+            data: reader.take(size)?,
+        };
+        reader.finish()?;
+        Ok(body)
+    }
+
+    fn finish(self) -> Result<(), ParseError> {
+        if self.data.is_empty() {
+            Ok(())
+        } else {
+            Err(ParseError::SizeMismatch)
+        }
+    }
+
+    /// Read the version, reserved byte, and view box shared by images and
+    /// sequences.
+    fn header(&mut self) -> Result<Size, ParseError> {
+        let version = self.u8()?;
+        if version != VERSION {
+            return Err(ParseError::UnsupportedVersion(version));
+        }
+        self.u8()?;
+        Ok(Size::new(self.i16()?, self.i16()?))
+    }
+
+    fn commands(&mut self) -> Result<Vec<Command>, ParseError> {
+        let count = self.u16()?;
+        (0..count).map(|_| self.command()).collect()
+    }
+
+    fn command(&mut self) -> Result<Command, ParseError> {
+        let kind = self.u8()?;
+        let flags = self.u8()?;
+        let stroke_color = Color::from_argb8(self.u8()?);
+        let stroke_width = self.u8()?;
+        let fill_color = Color::from_argb8(self.u8()?);
+        let open_or_radius = self.u16()?;
+        let shape = match kind {
+            TYPE_PATH => Shape::Path {
+                open: open_or_radius & PATH_OPEN != 0,
+            },
+            TYPE_CIRCLE => Shape::Circle {
+                radius: open_or_radius,
+            },
+            TYPE_PRECISE_PATH => Shape::PrecisePath {
+                open: open_or_radius & PATH_OPEN != 0,
+            },
+            kind => return Err(ParseError::UnknownCommandType(kind)),
+        };
+        let count = self.u16()?;
+        let points = (0..count)
+            .map(|_| Ok(Point::new(self.i16()?, self.i16()?)))
+            .collect::<Result<_, _>>()?;
+        Ok(Command {
+            shape,
+            hidden: flags & FLAG_HIDDEN != 0,
+            stroke_color,
+            stroke_width,
+            fill_color,
+            points,
+        })
+    }
+}
+
+struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    /// Start a file with `magic` and room for its size.
+    fn new(magic: &[u8; 4]) -> Writer {
+        let mut data = Vec::new();
+        data.extend_from_slice(magic);
+        data.extend_from_slice(&[0; 4]);
+        Writer { data }
+    }
+
+    /// Fill in the size of the body and return the file.
+    fn finish(mut self) -> Vec<u8> {
+        let size = (self.data.len() - 8) as u32;
+        self.data[4..8].copy_from_slice(&size.to_le_bytes());
+        self.data
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn i16(&mut self, value: i16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn header(&mut self, view_box: Size) {
+        self.u8(VERSION);
+        self.u8(0);
+        self.i16(view_box.w);
+        self.i16(view_box.h);
+    }
+
+    fn commands(&mut self, commands: &[Command]) {
+        self.u16(length(commands.len(), "commands"));
+        for command in commands {
+            self.command(command);
+        }
+    }
+
+    fn command(&mut self, command: &Command) {
+        let (kind, open_or_radius) = match command.shape {
+            Shape::Path { open } => (TYPE_PATH, if open { PATH_OPEN } else { 0 }),
+            Shape::Circle { radius } => (TYPE_CIRCLE, radius),
+            Shape::PrecisePath { open } => (TYPE_PRECISE_PATH, if open { PATH_OPEN } else { 0 }),
+        };
+        self.u8(kind);
+        self.u8(if command.hidden { FLAG_HIDDEN } else { 0 });
+        self.u8(command.stroke_color.argb8());
+        self.u8(command.stroke_width);
+        self.u8(command.fill_color.argb8());
+        self.u16(open_or_radius);
+        self.u16(length(command.points.len(), "points"));
+        for point in &command.points {
+            self.i16(point.x);
+            self.i16(point.y);
+        }
+    }
+}
+
+fn length(len: usize, what: &str) -> u16 {
+    u16::try_from(len).unwrap_or_else(|_| panic!("Too many {what} for a PDC file"))
+}
+
+impl Image {
+    /// Parse the contents of a `PDCI` file.
+    pub fn parse(data: &[u8]) -> Result<Image, ParseError> {
+        let mut reader = Reader::body(data, IMAGE_MAGIC)?;
+        let view_box = reader.header()?;
+        let commands = reader.commands()?;
+        reader.finish()?;
+        Ok(Image { view_box, commands })
+    }
+
+    /// Serialize the image as a `PDCI` file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 65535 commands, or points in a command.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new(IMAGE_MAGIC);
+        writer.header(self.view_box);
+        writer.commands(&self.commands);
+        writer.finish()
+    }
+}
+
+impl Sequence {
+    /// Parse the contents of a `PDCS` file.
+    pub fn parse(data: &[u8]) -> Result<Sequence, ParseError> {
+        let mut reader = Reader::body(data, SEQUENCE_MAGIC)?;
+        let view_box = reader.header()?;
+        let play_count = reader.u16()?;
+        let count = reader.u16()?;
+        let frames = (0..count)
+            .map(|_| {
+                Ok(Frame {
+                    duration: reader.u16()?,
+                    commands: reader.commands()?,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        reader.finish()?;
+        Ok(Sequence {
+            view_box,
+            play_count,
+            frames,
+        })
+    }
+
+    /// Serialize the sequence as a `PDCS` file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 65535 frames, commands in a frame, or
+    /// points in a command.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new(SEQUENCE_MAGIC);
+        writer.header(self.view_box);
+        writer.u16(self.play_count);
+        writer.u16(length(self.frames.len(), "frames"));
+        for frame in &self.frames {
+            writer.u16(frame.duration);
+            writer.commands(&frame.commands);
+        }
+        writer.finish()
+    }
+
+    /// The total length of one play of the sequence, in milliseconds.
+    pub fn total_duration(&self) -> u32 {
+        self.frames.iter().map(|frame| frame.duration as u32).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_alloc::vec;
+
+    use super::*;
+
+    const FERRIS: &[u8] = include_bytes!("../../../examples/hello-ferris/resources/ferris.pdc");
+
+    fn circle() -> Command {
+        Command {
+            shape: Shape::Circle { radius: 5 },
+            hidden: false,
+            stroke_color: Color::BLACK,
+            stroke_width: 1,
+            fill_color: Color::WHITE,
+            points: vec![Point::new(10, 10)],
+        }
+    }
+
+    #[test]
+    fn ferris_roundtrip() {
+        let image = Image::parse(FERRIS).unwrap();
+        assert!(!image.commands.is_empty());
+        assert_eq!(image.to_bytes(), FERRIS);
+    }
+
+    #[test]
+    fn sequence_roundtrip() {
+        let sequence = Sequence {
+            view_box: Size::new(20, 20),
+            play_count: 2,
+            frames: vec![
+                Frame {
+                    duration: 100,
+                    commands: vec![circle()],
+                },
+                Frame {
+                    duration: 50,
+                    commands: Vec::new(),
+                },
+            ],
+        };
+        assert_eq!(Sequence::parse(&sequence.to_bytes()), Ok(sequence));
+    }
+
+    #[test]
+    fn truncated() {
+        for len in 0..FERRIS.len() {
+            let result = Image::parse(&FERRIS[..len]);
+            assert!(
+                matches!(result, Err(ParseError::BadMagic | ParseError::Truncated)),
+                "{len} bytes: {result:?}",
+            );
+        }
+    }
+
+    #[test]
+    fn bad_magic() {
+        assert_eq!(Image::parse(b"PDCX"), Err(ParseError::BadMagic));
+        assert_eq!(Sequence::parse(FERRIS), Err(ParseError::BadMagic));
+    }
+
+    #[test]
+    fn wrong_size() {
+        let mut data = FERRIS.to_vec();
+        data.push(0);
+        assert_eq!(Image::parse(&data), Err(ParseError::SizeMismatch));
+
+        let size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+        data[4..8].copy_from_slice(&(size + 2).to_le_bytes());
+        assert_eq!(Image::parse(&data), Err(ParseError::Truncated));
+    }
+
+    #[test]
+    fn unknown_command_type() {
+        let image = Image {
+            view_box: Size::new(20, 20),
+            commands: vec![circle()],
+        };
+        let mut data = image.to_bytes();
+        // The first command follows the 8 byte file header, the 6 byte image
+        // header, and the command count.
+        data[16] = 9;
+        assert_eq!(Image::parse(&data), Err(ParseError::UnknownCommandType(9)));
+    }
+}
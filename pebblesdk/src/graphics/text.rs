@@ -0,0 +1,129 @@
+//! Options for laying out text.
+
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+use pebblesdk_sys::{
+    graphics_text_attributes_create, graphics_text_attributes_destroy,
+    graphics_text_attributes_enable_paging, graphics_text_attributes_enable_screen_text_flow,
+    graphics_text_attributes_restore_default_paging,
+    graphics_text_attributes_restore_default_text_flow, GTextAttributes,
+};
+use pebblesdk_sys::{
+    GTextAlignment, GTextAlignmentCenter, GTextAlignmentLeft, GTextAlignmentRight,
+    GTextOverflowMode, GTextOverflowModeFill, GTextOverflowModeTrailingEllipsis,
+    GTextOverflowModeWordWrap,
+};
+
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+use crate::graphics::{Point, Rect};
+
+/// How each line of text is placed horizontally within its box.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextAlignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlignment {
+    pub(crate) fn raw(self) -> GTextAlignment {
+        match self {
+            TextAlignment::Left => GTextAlignmentLeft,
+            TextAlignment::Center => GTextAlignmentCenter,
+            TextAlignment::Right => GTextAlignmentRight,
+        }
+    }
+}
+
+/// What happens to text that doesn't fit within its box.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextOverflowMode {
+    /// Wrap onto new lines at word boundaries, clipping lines past the
+    /// bottom of the box.
+    #[default]
+    WordWrap,
+    /// Wrap like [`TextOverflowMode::WordWrap`], but end the last line that
+    /// fits with an ellipsis.
+    TrailingEllipsis,
+    /// Like [`TextOverflowMode::TrailingEllipsis`], but trims leading and
+    /// trailing newlines and treats any others as spaces.
+    Fill,
+}
+
+impl TextOverflowMode {
+    pub(crate) fn raw(self) -> GTextOverflowMode {
+        match self {
+            TextOverflowMode::WordWrap => GTextOverflowModeWordWrap,
+            TextOverflowMode::TrailingEllipsis => GTextOverflowModeTrailingEllipsis,
+            TextOverflowMode::Fill => GTextOverflowModeFill,
+        }
+    }
+}
+
+/// Extra layout settings for text, such as flowing it around the edge of a
+/// round screen.
+///
+/// ```ignore
+/// let mut attributes = TextAttributes::new();
+/// attributes.enable_screen_text_flow(8);
+/// let size = font.content_size_with_attributes(text, bounds, overflow, alignment, &attributes);
+/// ```
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+pub struct TextAttributes {
+    raw: *mut GTextAttributes,
+}
+
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+impl TextAttributes {
+    /// Create attributes laying text out like it would be without them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory.
+    pub fn new() -> TextAttributes {
+        let raw = unsafe { graphics_text_attributes_create() };
+        assert!(!raw.is_null(), "Failed to create text attributes");
+        TextAttributes { raw }
+    }
+
+    /// Get the underlying PebbleOS text attributes, for use with
+    /// [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut GTextAttributes {
+        self.raw
+    }
+
+    /// Shorten lines to follow the edge of the screen, keeping `inset`
+    /// pixels away from it. This only has an effect on round screens.
+    pub fn enable_screen_text_flow(&mut self, inset: u8) {
+        unsafe { graphics_text_attributes_enable_screen_text_flow(self.raw, inset) }
+    }
+
+    pub fn restore_default_text_flow(&mut self) {
+        unsafe { graphics_text_attributes_restore_default_text_flow(self.raw) }
+    }
+
+    /// Avoid cutting lines in half at page boundaries, where the text's box
+    /// is at `origin` on screen and pages are the size of `page`, such as
+    /// when scrolling it one screen at a time.
+    pub fn enable_paging(&mut self, origin: Point, page: Rect) {
+        unsafe { graphics_text_attributes_enable_paging(self.raw, origin.into(), page.into()) }
+    }
+
+    pub fn restore_default_paging(&mut self) {
+        unsafe { graphics_text_attributes_restore_default_paging(self.raw) }
+    }
+}
+
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+impl Default for TextAttributes {
+    fn default() -> TextAttributes {
+        TextAttributes::new()
+    }
+}
+
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+impl Drop for TextAttributes {
+    fn drop(&mut self) {
+        unsafe { graphics_text_attributes_destroy(self.raw) }
+    }
+}
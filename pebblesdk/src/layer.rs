@@ -0,0 +1,95 @@
+//! The building blocks of a window's contents.
+
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+use pebblesdk_sys::layer_get_unobstructed_bounds;
+use pebblesdk_sys::{
+    layer_add_child, layer_get_bounds, layer_get_frame, layer_get_hidden, layer_mark_dirty,
+    layer_remove_from_parent, layer_set_bounds, layer_set_frame, layer_set_hidden,
+    Layer as RawLayer,
+};
+
+use crate::graphics::Rect;
+
+pub mod action_bar;
+pub mod menu;
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+pub mod pdc;
+pub mod scroll;
+pub mod simple_menu;
+pub mod status_bar;
+
+/// A layer in PebbleOS's layer hierarchy.
+///
+/// Only ever handled by reference, borrowed from whatever owns the layer (such
+/// as a [`Window`](crate::window::Window) for its root layer).
+#[repr(transparent)]
+pub struct Layer(RawLayer);
+
+impl Layer {
+    /// # Safety
+    ///
+    /// `raw` must point to a valid layer that outlives the lifetime `'a`.
+    pub(crate) unsafe fn from_ptr<'a>(raw: *const RawLayer) -> &'a Layer {
+        unsafe { &*(raw as *const Layer) }
+    }
+
+    /// Get the underlying PebbleOS layer, for use with [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut RawLayer {
+        self as *const Layer as *mut RawLayer
+    }
+
+    /// The layer's position and size, relative to its parent.
+    pub fn frame(&self) -> Rect {
+        unsafe { layer_get_frame(self.as_ptr()) }.into()
+    }
+
+    pub fn set_frame(&self, frame: Rect) {
+        unsafe { layer_set_frame(self.as_ptr(), frame.into()) }
+    }
+
+    /// The area the layer draws into, relative to its frame.
+    pub fn bounds(&self) -> Rect {
+        unsafe { layer_get_bounds(self.as_ptr()) }.into()
+    }
+
+    pub fn set_bounds(&self, bounds: Rect) {
+        unsafe { layer_set_bounds(self.as_ptr(), bounds.into()) }
+    }
+
+    /// The part of the layer's bounds not covered by system overlays, such
+    /// as a Timeline Quick View.
+    #[cfg(not(pebble_sdk_platform = "aplite"))]
+    pub fn unobstructed_bounds(&self) -> Rect {
+        unsafe { layer_get_unobstructed_bounds(self.as_ptr()) }.into()
+    }
+
+    /// The part of the layer's bounds not covered by system overlays. Nothing
+    /// covers layers on Aplite, so this is always the full bounds.
+    #[cfg(pebble_sdk_platform = "aplite")]
+    pub fn unobstructed_bounds(&self) -> Rect {
+        self.bounds()
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        unsafe { layer_get_hidden(self.as_ptr()) }
+    }
+
+    pub fn set_hidden(&self, hidden: bool) {
+        unsafe { layer_set_hidden(self.as_ptr(), hidden) }
+    }
+
+    /// Schedule the layer to be redrawn.
+    pub fn mark_dirty(&self) {
+        unsafe { layer_mark_dirty(self.as_ptr()) }
+    }
+
+    /// Add `child` on top of this layer's other children. A layer is removed
+    /// from its parent automatically when it is destroyed.
+    pub fn add_child(&self, child: &Layer) {
+        unsafe { layer_add_child(self.as_ptr(), child.as_ptr()) }
+    }
+
+    pub fn remove_from_parent(&self) {
+        unsafe { layer_remove_from_parent(self.as_ptr()) }
+    }
+}
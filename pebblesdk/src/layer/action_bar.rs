@@ -0,0 +1,219 @@
+//! A vertical bar of button icons along the right edge of a window.
+
+use core::ptr::null;
+
+use pebblesdk_sys::{
+    action_bar_layer_add_to_window, action_bar_layer_create, action_bar_layer_destroy,
+    action_bar_layer_get_layer, action_bar_layer_remove_from_window,
+    action_bar_layer_set_background_color, action_bar_layer_set_click_config_provider,
+    action_bar_layer_set_context, action_bar_layer_set_icon_animated,
+    action_bar_layer_set_icon_press_animation, ActionBarLayer as RawActionBarLayer,
+    ActionBarLayerIconPressAnimation as RawIconPressAnimation,
+    ActionBarLayerIconPressAnimationMoveDown, ActionBarLayerIconPressAnimationMoveLeft,
+    ActionBarLayerIconPressAnimationMoveRight, ActionBarLayerIconPressAnimationMoveUp,
+    ActionBarLayerIconPressAnimationNone,
+};
+
+use crate::click::{click_config_provider, ButtonId, ClickConfig, SharedClickConfig};
+use crate::graphics::{Bitmap, Color};
+use crate::layer::Layer;
+use crate::window::Window;
+
+/// The width of the action bar.
+#[cfg(not(pebble_sdk_platform = "chalk"))]
+pub const ACTION_BAR_WIDTH: i16 = 30;
+/// The width of the action bar.
+#[cfg(pebble_sdk_platform = "chalk")]
+pub const ACTION_BAR_WIDTH: i16 = 40;
+
+/// How an icon moves when its button is pressed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IconPressAnimation {
+    None,
+    MoveLeft,
+    MoveUp,
+    MoveRight,
+    MoveDown,
+}
+
+impl IconPressAnimation {
+    fn raw(self) -> RawIconPressAnimation {
+        match self {
+            IconPressAnimation::None => ActionBarLayerIconPressAnimationNone,
+            IconPressAnimation::MoveLeft => ActionBarLayerIconPressAnimationMoveLeft,
+            IconPressAnimation::MoveUp => ActionBarLayerIconPressAnimationMoveUp,
+            IconPressAnimation::MoveRight => ActionBarLayerIconPressAnimationMoveRight,
+            IconPressAnimation::MoveDown => ActionBarLayerIconPressAnimationMoveDown,
+        }
+    }
+}
+
+/// Icons for the up, select, and down buttons, the slots indexed by
+/// [`ButtonId`] minus one.
+#[derive(Default)]
+struct Icons {
+    current: [Option<Bitmap>; 3],
+    // The icon being animated out after a change, which PebbleOS keeps
+    // drawing until the next change.
+    previous: [Option<Bitmap>; 3],
+}
+
+/// An action bar with an icon and click handlers for each of the up, select,
+/// and down buttons.
+///
+/// ```ignore
+/// let mut action_bar = ActionBarLayer::new();
+/// action_bar.set_icon(ButtonId::Up, Bitmap::with_resource(RESOURCE_ID_PLUS).unwrap());
+/// action_bar.set_click_config(
+///     ClickConfig::new().single_click(ButtonId::Up, |_| counter.increment()),
+/// );
+/// action_bar.add_to_window(&window);
+/// ```
+pub struct ActionBarLayer {
+    raw: *mut RawActionBarLayer,
+    icons: Icons,
+    click_config: Option<SharedClickConfig>,
+}
+
+impl ActionBarLayer {
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory.
+    pub fn new() -> ActionBarLayer {
+        let raw = unsafe { action_bar_layer_create() };
+        assert!(!raw.is_null(), "Failed to create action bar layer");
+        ActionBarLayer {
+            raw,
+            icons: Icons::default(),
+            click_config: None,
+        }
+    }
+
+    pub fn layer(&self) -> &Layer {
+        unsafe { Layer::from_ptr(action_bar_layer_get_layer(self.raw)) }
+    }
+
+    /// Get the underlying PebbleOS action bar layer, for use with
+    /// [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut RawActionBarLayer {
+        self.raw
+    }
+
+    /// Add the action bar to the right edge of `window`, letting it handle
+    /// the window's buttons.
+    pub fn add_to_window(&self, window: &Window) {
+        unsafe { action_bar_layer_add_to_window(self.raw, window.as_ptr()) }
+    }
+
+    pub fn remove_from_window(&self) {
+        unsafe { action_bar_layer_remove_from_window(self.raw) }
+    }
+
+    /// Set the handlers called when buttons are pressed while the action bar
+    /// is in the top window, replacing any previous configuration.
+    pub fn set_click_config(&mut self, config: ClickConfig) {
+        let config = SharedClickConfig::new(config);
+        unsafe {
+            // The action bar passes its context to both the provider and the
+            // handlers it subscribes.
+            action_bar_layer_set_context(self.raw, config.context());
+            action_bar_layer_set_click_config_provider(self.raw, Some(click_config_provider));
+        }
+        // Only release the old config after PebbleOS has let go of it.
+        self.click_config = Some(config);
+    }
+
+    /// Show `icon` next to `button`, replacing any previous icon.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `button` is [`ButtonId::Back`].
+    pub fn set_icon(&mut self, button: ButtonId, icon: Bitmap) {
+        self.replace_icon(button, Some(icon), false);
+    }
+
+    /// Like [`set_icon`](ActionBarLayer::set_icon), but animating the old icon
+    /// out and the new one in.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `button` is [`ButtonId::Back`].
+    pub fn set_icon_animated(&mut self, button: ButtonId, icon: Bitmap) {
+        self.replace_icon(button, Some(icon), true);
+    }
+
+    /// Remove the icon next to `button`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `button` is [`ButtonId::Back`].
+    pub fn clear_icon(&mut self, button: ButtonId) {
+        self.replace_icon(button, None, false);
+    }
+
+    fn replace_icon(&mut self, button: ButtonId, icon: Option<Bitmap>, animated: bool) {
+        let slot = icon_slot(button);
+        unsafe {
+            action_bar_layer_set_icon_animated(
+                self.raw,
+                button.raw(),
+                icon.as_ref().map_or(null(), |icon| icon.as_ptr()),
+                animated,
+            )
+        };
+        let previous = core::mem::replace(&mut self.icons.current[slot], icon);
+        self.icons.previous[slot] = previous;
+    }
+
+    /// The icon next to `button`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `button` is [`ButtonId::Back`].
+    pub fn icon(&self, button: ButtonId) -> Option<&Bitmap> {
+        self.icons.current[icon_slot(button)].as_ref()
+    }
+
+    /// Set how the icon next to `button` moves when the button is pressed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `button` is [`ButtonId::Back`].
+    pub fn set_icon_press_animation(&self, button: ButtonId, animation: IconPressAnimation) {
+        icon_slot(button);
+        unsafe {
+            action_bar_layer_set_icon_press_animation(self.raw, button.raw(), animation.raw())
+        }
+    }
+
+    pub fn set_background_color(&self, color: Color) {
+        unsafe { action_bar_layer_set_background_color(self.raw, color.raw()) }
+    }
+}
+
+fn icon_slot(button: ButtonId) -> usize {
+    assert!(
+        button != ButtonId::Back,
+        "The action bar has no icon for the back button"
+    );
+    button as usize - 1
+}
+
+impl Default for ActionBarLayer {
+    fn default() -> ActionBarLayer {
+        ActionBarLayer::new()
+    }
+}
+
+impl AsRef<Layer> for ActionBarLayer {
+    fn as_ref(&self) -> &Layer {
+        self.layer()
+    }
+}
+
+impl Drop for ActionBarLayer {
+    fn drop(&mut self) {
+        // Destroy the layer before the icons it draws are dropped.
+        unsafe { action_bar_layer_destroy(self.raw) }
+    }
+}
@@ -0,0 +1,528 @@
+//! Scrolling lists of selectable rows, grouped into sections.
+
+use core::cell::{Cell, Ref, RefCell, RefMut};
+use core::ffi::{c_void, CStr};
+use core::ptr::{null, null_mut};
+
+use rust_alloc::boxed::Box;
+
+use pebblesdk_sys::{
+    layer_remove_from_parent, menu_cell_basic_draw, menu_cell_basic_header_draw,
+    menu_cell_layer_is_highlighted, menu_cell_title_draw, menu_layer_create, menu_layer_destroy,
+    menu_layer_get_layer, menu_layer_get_selected_index, menu_layer_is_index_selected,
+    menu_layer_reload_data, menu_layer_set_callbacks, menu_layer_set_click_config_onto_window,
+    menu_layer_set_highlight_colors, menu_layer_set_normal_colors, menu_layer_set_selected_index,
+    GContext, Layer as RawLayer, MenuIndex as RawMenuIndex, MenuLayer as RawMenuLayer,
+    MenuLayerCallbacks, MenuRowAlign as RawMenuRowAlign, MenuRowAlignBottom, MenuRowAlignCenter,
+    MenuRowAlignNone, MenuRowAlignTop,
+};
+
+use crate::graphics::{Bitmap, Color, GraphicsContext, Rect};
+use crate::layer::Layer;
+use crate::timer::defer;
+use crate::window::Window;
+
+/// The height of a cell drawn with [`MenuCell::basic_draw`] on rectangular
+/// displays.
+pub const MENU_CELL_BASIC_CELL_HEIGHT: i16 = 44;
+/// The height of a header drawn with [`MenuCell::header_draw`].
+pub const MENU_CELL_BASIC_HEADER_HEIGHT: i16 = 16;
+/// The height of the selected cell on round displays.
+pub const MENU_CELL_ROUND_FOCUSED_SHORT_CELL_HEIGHT: i16 = 68;
+/// The height of unselected cells on round displays.
+pub const MENU_CELL_ROUND_UNFOCUSED_SHORT_CELL_HEIGHT: i16 = 24;
+
+/// The position of a row in a menu.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MenuIndex {
+    pub section: u16,
+    pub row: u16,
+}
+
+impl MenuIndex {
+    pub const fn new(section: u16, row: u16) -> MenuIndex {
+        MenuIndex { section, row }
+    }
+}
+
+impl From<RawMenuIndex> for MenuIndex {
+    fn from(index: RawMenuIndex) -> MenuIndex {
+        MenuIndex::new(index.section, index.row)
+    }
+}
+
+impl From<MenuIndex> for RawMenuIndex {
+    fn from(index: MenuIndex) -> RawMenuIndex {
+        RawMenuIndex {
+            section: index.section,
+            row: index.row,
+        }
+    }
+}
+
+/// Where to scroll a newly selected row to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuRowAlign {
+    /// Scroll as little as possible to bring the row on screen.
+    None,
+    Center,
+    Top,
+    Bottom,
+}
+
+impl MenuRowAlign {
+    pub(crate) fn raw(self) -> RawMenuRowAlign {
+        match self {
+            MenuRowAlign::None => MenuRowAlignNone,
+            MenuRowAlign::Center => MenuRowAlignCenter,
+            MenuRowAlign::Top => MenuRowAlignTop,
+            MenuRowAlign::Bottom => MenuRowAlignBottom,
+        }
+    }
+}
+
+/// A cell (row or header) being drawn by a [`MenuDataSource`].
+pub struct MenuCell<'a> {
+    ctx: &'a mut GraphicsContext,
+    layer: &'a Layer,
+}
+
+impl MenuCell<'_> {
+    pub fn graphics(&mut self) -> &mut GraphicsContext {
+        self.ctx
+    }
+
+    pub fn layer(&self) -> &Layer {
+        self.layer
+    }
+
+    /// The area to draw the cell in.
+    pub fn bounds(&self) -> Rect {
+        self.layer.bounds()
+    }
+
+    /// True if the cell is the selected row.
+    pub fn is_highlighted(&self) -> bool {
+        unsafe { menu_cell_layer_is_highlighted(self.layer.as_ptr()) }
+    }
+
+    /// Draw a row in the standard style, with a title and optional subtitle
+    /// and icon.
+    pub fn basic_draw(&mut self, title: &CStr, subtitle: Option<&CStr>, icon: Option<&Bitmap>) {
+        unsafe {
+            menu_cell_basic_draw(
+                self.ctx.as_ptr(),
+                self.layer.as_ptr(),
+                title.as_ptr(),
+                subtitle.map_or(null(), CStr::as_ptr),
+                icon.map_or(null_mut(), Bitmap::as_ptr),
+            )
+        }
+    }
+
+    /// Draw a row with only a title, in a larger font than
+    /// [`basic_draw`](MenuCell::basic_draw).
+    pub fn title_draw(&mut self, title: &CStr) {
+        unsafe { menu_cell_title_draw(self.ctx.as_ptr(), self.layer.as_ptr(), title.as_ptr()) }
+    }
+
+    /// Draw a section header in the standard style.
+    pub fn header_draw(&mut self, title: &CStr) {
+        unsafe {
+            menu_cell_basic_header_draw(self.ctx.as_ptr(), self.layer.as_ptr(), title.as_ptr())
+        }
+    }
+}
+
+/// Access to a [`MenuLayer`] from within the callbacks of its data source.
+pub struct MenuHandle {
+    raw: *mut RawMenuLayer,
+    reload: bool,
+}
+
+impl MenuHandle {
+    /// Reload the menu's data once the current callback returns.
+    pub fn reload_data(&mut self) {
+        self.reload = true;
+    }
+
+    pub fn selected_index(&self) -> MenuIndex {
+        unsafe { menu_layer_get_selected_index(self.raw) }.into()
+    }
+}
+
+/// The contents of a [`MenuLayer`], and how to respond to the user.
+///
+/// Only [`num_rows`](MenuDataSource::num_rows) and
+/// [`draw_row`](MenuDataSource::draw_row) are required, which is enough for a
+/// menu with a single section and no headers.
+pub trait MenuDataSource: 'static {
+    fn num_sections(&mut self) -> u16 {
+        1
+    }
+
+    fn num_rows(&mut self, section: u16) -> u16;
+
+    /// The height of a row. Defaults to the height used by
+    /// [`MenuCell::basic_draw`], or on round displays, a taller cell for the
+    /// selected row.
+    fn cell_height(&mut self, index: MenuIndex, selected: bool) -> i16 {
+        let _ = index;
+        if cfg!(pebble_sdk_platform = "chalk") {
+            if selected {
+                MENU_CELL_ROUND_FOCUSED_SHORT_CELL_HEIGHT
+            } else {
+                MENU_CELL_ROUND_UNFOCUSED_SHORT_CELL_HEIGHT
+            }
+        } else {
+            MENU_CELL_BASIC_CELL_HEIGHT
+        }
+    }
+
+    /// The height of a section's header. Defaults to 0, hiding headers.
+    fn header_height(&mut self, section: u16) -> i16 {
+        let _ = section;
+        0
+    }
+
+    fn draw_row(&mut self, cell: &mut MenuCell<'_>, index: MenuIndex);
+
+    fn draw_header(&mut self, cell: &mut MenuCell<'_>, section: u16) {
+        let _ = (cell, section);
+    }
+
+    /// Called when the select button is pressed on a row.
+    fn select_click(&mut self, menu: &mut MenuHandle, index: MenuIndex) {
+        let _ = (menu, index);
+    }
+
+    /// Called when the select button is held on a row.
+    fn select_long_click(&mut self, menu: &mut MenuHandle, index: MenuIndex) {
+        let _ = (menu, index);
+    }
+
+    /// Called before the selection moves, allowing the new index to be
+    /// changed (such as to skip over rows that can't be selected).
+    fn selection_will_change(&mut self, new_index: &mut MenuIndex, old_index: MenuIndex) {
+        let _ = (new_index, old_index);
+    }
+
+    /// Called after the selection has moved.
+    fn selection_changed(
+        &mut self,
+        menu: &mut MenuHandle,
+        new_index: MenuIndex,
+        old_index: MenuIndex,
+    ) {
+        let _ = (menu, new_index, old_index);
+    }
+}
+
+/// A menu whose rows are provided by a [`MenuDataSource`] it owns.
+///
+/// The menu can be dropped from one of its own callbacks, such as to close it
+/// when a row is selected. It is taken off screen right away, but destroyed,
+/// along with the data source, once control returns to the event loop.
+pub struct MenuLayer<D: MenuDataSource> {
+    raw: *mut RawMenuLayer,
+    // Allocated separately so the context pointer given to PebbleOS stays
+    // valid if the `MenuLayer` is moved, or dropped by a callback.
+    shared: *mut Shared<D>,
+}
+
+struct Shared<D> {
+    // The number of callbacks running.
+    dispatching: Cell<u32>,
+    data: RefCell<D>,
+}
+
+// Data sources are borrowed for each callback, which could in theory be called
+// while the data is already borrowed (e.g. by a `MenuLayer` method that calls
+// back into the menu). Rather than panic inside a callback from PebbleOS, fall
+// back to an empty menu.
+//
+// The callback is counted as dispatching so that dropping the layer from it
+// is deferred. See `MenuLayer::drop`.
+unsafe fn dispatch<D, R>(context: *mut c_void, f: impl FnOnce(&mut D) -> R) -> Option<R> {
+    let shared = unsafe { &*(context as *const Shared<D>) };
+    let mut data = shared.data.try_borrow_mut().ok()?;
+    shared.dispatching.set(shared.dispatching.get() + 1);
+    let result = f(&mut data);
+    drop(data);
+    shared.dispatching.set(shared.dispatching.get() - 1);
+    Some(result)
+}
+
+fn with_handle<D, F>(menu_layer: *mut RawMenuLayer, context: *mut c_void, f: F)
+where
+    F: FnOnce(&mut D, &mut MenuHandle),
+{
+    let mut handle = MenuHandle {
+        raw: menu_layer,
+        reload: false,
+    };
+    unsafe { dispatch::<D, _>(context, |data| f(data, &mut handle)) };
+    if handle.reload {
+        unsafe { menu_layer_reload_data(menu_layer) };
+    }
+}
+
+unsafe extern "C" fn get_num_sections<D: MenuDataSource>(
+    _menu_layer: *mut RawMenuLayer,
+    context: *mut c_void,
+) -> u16 {
+    unsafe { dispatch::<D, _>(context, |data| data.num_sections()) }.unwrap_or(0)
+}
+
+unsafe extern "C" fn get_num_rows<D: MenuDataSource>(
+    _menu_layer: *mut RawMenuLayer,
+    section: u16,
+    context: *mut c_void,
+) -> u16 {
+    unsafe { dispatch::<D, _>(context, |data| data.num_rows(section)) }.unwrap_or(0)
+}
+
+unsafe extern "C" fn get_cell_height<D: MenuDataSource>(
+    menu_layer: *mut RawMenuLayer,
+    index: *mut RawMenuIndex,
+    context: *mut c_void,
+) -> i16 {
+    let selected = unsafe { menu_layer_is_index_selected(menu_layer, index) };
+    let index = unsafe { *index }.into();
+    unsafe { dispatch::<D, _>(context, |data| data.cell_height(index, selected)) }.unwrap_or(0)
+}
+
+unsafe extern "C" fn get_header_height<D: MenuDataSource>(
+    _menu_layer: *mut RawMenuLayer,
+    section: u16,
+    context: *mut c_void,
+) -> i16 {
+    unsafe { dispatch::<D, _>(context, |data| data.header_height(section)) }.unwrap_or(0)
+}
+
+unsafe extern "C" fn draw_row<D: MenuDataSource>(
+    ctx: *mut GContext,
+    cell_layer: *const RawLayer,
+    index: *mut RawMenuIndex,
+    context: *mut c_void,
+) {
+    let mut cell = unsafe {
+        MenuCell {
+            ctx: GraphicsContext::from_ptr(ctx),
+            layer: Layer::from_ptr(cell_layer),
+        }
+    };
+    let index = unsafe { *index }.into();
+    unsafe { dispatch::<D, _>(context, |data| data.draw_row(&mut cell, index)) };
+}
+
+unsafe extern "C" fn draw_header<D: MenuDataSource>(
+    ctx: *mut GContext,
+    cell_layer: *const RawLayer,
+    section: u16,
+    context: *mut c_void,
+) {
+    let mut cell = unsafe {
+        MenuCell {
+            ctx: GraphicsContext::from_ptr(ctx),
+            layer: Layer::from_ptr(cell_layer),
+        }
+    };
+    unsafe { dispatch::<D, _>(context, |data| data.draw_header(&mut cell, section)) };
+}
+
+unsafe extern "C" fn select_click<D: MenuDataSource>(
+    menu_layer: *mut RawMenuLayer,
+    index: *mut RawMenuIndex,
+    context: *mut c_void,
+) {
+    let index = unsafe { *index }.into();
+    with_handle::<D, _>(menu_layer, context, |data, menu| {
+        data.select_click(menu, index)
+    });
+}
+
+unsafe extern "C" fn select_long_click<D: MenuDataSource>(
+    menu_layer: *mut RawMenuLayer,
+    index: *mut RawMenuIndex,
+    context: *mut c_void,
+) {
+    let index = unsafe { *index }.into();
+    with_handle::<D, _>(menu_layer, context, |data, menu| {
+        data.select_long_click(menu, index)
+    });
+}
+
+unsafe extern "C" fn selection_will_change<D: MenuDataSource>(
+    _menu_layer: *mut RawMenuLayer,
+    new_index: *mut RawMenuIndex,
+    old_index: RawMenuIndex,
+    context: *mut c_void,
+) {
+    let mut index = unsafe { *new_index }.into();
+    unsafe {
+        dispatch::<D, _>(context, |data| {
+            data.selection_will_change(&mut index, old_index.into())
+        })
+    };
+    unsafe { *new_index = index.into() };
+}
+
+unsafe extern "C" fn selection_changed<D: MenuDataSource>(
+    menu_layer: *mut RawMenuLayer,
+    new_index: RawMenuIndex,
+    old_index: RawMenuIndex,
+    context: *mut c_void,
+) {
+    with_handle::<D, _>(menu_layer, context, |data, menu| {
+        data.selection_changed(menu, new_index.into(), old_index.into())
+    });
+}
+
+impl<D: MenuDataSource> MenuLayer<D> {
+    /// Create a menu layer showing the contents of `data`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory.
+    pub fn new(frame: Rect, data: D) -> MenuLayer<D> {
+        let raw = unsafe { menu_layer_create(frame.into()) };
+        assert!(!raw.is_null(), "Failed to create menu layer");
+        let shared = Box::into_raw(Box::new(Shared {
+            dispatching: Cell::new(0),
+            data: RefCell::new(data),
+        }));
+        unsafe {
+            menu_layer_set_callbacks(
+                raw,
+                shared as *mut c_void,
+                MenuLayerCallbacks {
+                    get_num_sections: Some(get_num_sections::<D>),
+                    get_num_rows: Some(get_num_rows::<D>),
+                    get_cell_height: Some(get_cell_height::<D>),
+                    get_header_height: Some(get_header_height::<D>),
+                    draw_row: Some(draw_row::<D>),
+                    draw_header: Some(draw_header::<D>),
+                    select_click: Some(select_click::<D>),
+                    select_long_click: Some(select_long_click::<D>),
+                    selection_changed: Some(selection_changed::<D>),
+                    get_separator_height: None,
+                    draw_separator: None,
+                    selection_will_change: Some(selection_will_change::<D>),
+                    draw_background: None,
+                },
+            )
+        };
+        MenuLayer { raw, shared }
+    }
+
+    pub fn layer(&self) -> &Layer {
+        unsafe { Layer::from_ptr(menu_layer_get_layer(self.raw)) }
+    }
+
+    /// Get the underlying PebbleOS menu layer, for use with [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut RawMenuLayer {
+        self.raw
+    }
+
+    fn data_cell(&self) -> &RefCell<D> {
+        unsafe { &(*self.shared).data }
+    }
+
+    /// Borrow the data source.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data source is currently mutably borrowed.
+    pub fn data(&self) -> Ref<'_, D> {
+        self.data_cell().borrow()
+    }
+
+    /// Mutably borrow the data source. If the number or size of rows
+    /// changes, use [`update_data`](MenuLayer::update_data) instead, or drop
+    /// the borrow before calling [`reload_data`](MenuLayer::reload_data).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data source is currently borrowed.
+    pub fn data_mut(&self) -> RefMut<'_, D> {
+        self.data_cell().borrow_mut()
+    }
+
+    /// Let the menu handle the up, down, and select buttons of `window`,
+    /// replacing the window's click configuration.
+    pub fn set_click_config_onto_window(&self, window: &Window) {
+        unsafe { menu_layer_set_click_config_onto_window(self.raw, window.as_ptr()) }
+    }
+
+    /// Change the data source with `f`, then reload the menu.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data source is currently borrowed.
+    pub fn update_data<R>(&self, f: impl FnOnce(&mut D) -> R) -> R {
+        let result = f(&mut self.data_cell().borrow_mut());
+        self.reload_data();
+        result
+    }
+
+    /// Query the data source again for the menu's contents, and redraw.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data source is currently borrowed, which would leave
+    /// the menu empty.
+    pub fn reload_data(&self) {
+        assert!(
+            self.data_cell().try_borrow_mut().is_ok(),
+            "Menu data source is borrowed while reloading"
+        );
+        unsafe { menu_layer_reload_data(self.raw) }
+    }
+
+    pub fn selected_index(&self) -> MenuIndex {
+        unsafe { menu_layer_get_selected_index(self.raw) }.into()
+    }
+
+    pub fn set_selected_index(&self, index: MenuIndex, align: MenuRowAlign, animated: bool) {
+        unsafe { menu_layer_set_selected_index(self.raw, index.into(), align.raw(), animated) }
+    }
+
+    /// Set the colors of unselected rows.
+    pub fn set_normal_colors(&self, background: Color, foreground: Color) {
+        unsafe { menu_layer_set_normal_colors(self.raw, background.raw(), foreground.raw()) }
+    }
+
+    /// Set the colors of the selected row.
+    pub fn set_highlight_colors(&self, background: Color, foreground: Color) {
+        unsafe { menu_layer_set_highlight_colors(self.raw, background.raw(), foreground.raw()) }
+    }
+}
+
+impl<D: MenuDataSource> AsRef<Layer> for MenuLayer<D> {
+    fn as_ref(&self) -> &Layer {
+        self.layer()
+    }
+}
+
+impl<D: MenuDataSource> Drop for MenuLayer<D> {
+    fn drop(&mut self) {
+        let (raw, shared) = (self.raw, self.shared);
+        if unsafe { (*shared).dispatching.get() } == 0 {
+            destroy(raw, shared);
+            return;
+        }
+        // Dropped from one of its own callbacks, while PebbleOS is still using
+        // the menu and the data source is borrowed. Take the layer off screen
+        // now, and destroy both once control returns to the event loop.
+        unsafe { layer_remove_from_parent(menu_layer_get_layer(raw)) };
+        defer(move || destroy(raw, shared));
+    }
+}
+
+fn destroy<D>(raw: *mut RawMenuLayer, shared: *mut Shared<D>) {
+    unsafe {
+        menu_layer_destroy(raw);
+        drop(Box::from_raw(shared));
+    }
+}
@@ -0,0 +1,198 @@
+//! A layer showing a Pebble Draw Command image or animation.
+
+use core::cell::{Cell, RefCell, RefMut};
+use core::mem::size_of;
+
+use rust_alloc::boxed::Box;
+
+use pebblesdk_sys::{
+    layer_create_with_data, layer_destroy, layer_get_data, layer_mark_dirty, layer_set_update_proc,
+    GContext, Layer as RawLayer,
+};
+
+use crate::graphics::draw_command::{DrawCommandImage, DrawCommandSequence, INFINITE};
+use crate::graphics::{GraphicsContext, Point, Rect};
+use crate::layer::Layer;
+use crate::timer::Interval;
+
+/// How often a playing sequence is redrawn.
+const FRAME_INTERVAL_MS: u32 = 33;
+
+enum Content {
+    Image(DrawCommandImage),
+    Sequence(DrawCommandSequence),
+}
+
+struct State {
+    content: RefCell<Content>,
+    offset: Cell<Point>,
+    elapsed_ms: Cell<u32>,
+    playback: RefCell<Option<Interval>>,
+}
+
+impl State {
+    fn is_finished(&self) -> bool {
+        match &*self.content.borrow() {
+            Content::Image(_) => true,
+            Content::Sequence(sequence) => {
+                let play_count = sequence.play_count();
+                play_count != INFINITE
+                    && self.elapsed_ms.get() >= sequence.total_duration().saturating_mul(play_count)
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn update_proc(layer: *mut RawLayer, ctx: *mut GContext) {
+    let state = unsafe { &**(layer_get_data(layer) as *const *const State) };
+    let ctx = unsafe { GraphicsContext::from_ptr(ctx) };
+    let Ok(content) = state.content.try_borrow() else {
+        return;
+    };
+    match &*content {
+        Content::Image(image) => image.draw(ctx, state.offset.get()),
+        Content::Sequence(sequence) => {
+            if let Some(frame) = sequence.frame_by_elapsed(state.elapsed_ms.get()) {
+                sequence.draw_frame(ctx, frame, state.offset.get());
+            }
+        }
+    }
+}
+
+/// A layer drawing a [`DrawCommandImage`], or playing a
+/// [`DrawCommandSequence`] frame by frame.
+///
+/// ```ignore
+/// let sequence = DrawCommandSequence::with_resource(RESOURCE_ID_CLOCK).unwrap();
+/// let clock = PdcLayer::with_sequence(Rect::new(0, 0, 80, 80), sequence);
+/// window.root_layer().add_child(clock.layer());
+/// clock.play();
+/// ```
+pub struct PdcLayer {
+    raw: *mut RawLayer,
+    state: Box<State>,
+}
+
+impl PdcLayer {
+    fn new(frame: Rect, content: Content) -> PdcLayer {
+        let raw = unsafe { layer_create_with_data(frame.into(), size_of::<*const State>()) };
+        assert!(!raw.is_null(), "Failed to create layer");
+        let state = Box::new(State {
+            content: RefCell::new(content),
+            offset: Cell::new(Point::default()),
+            elapsed_ms: Cell::new(0),
+            playback: RefCell::new(None),
+        });
+        unsafe {
+            *(layer_get_data(raw) as *mut *const State) = &*state;
+            layer_set_update_proc(raw, Some(update_proc));
+        }
+        PdcLayer { raw, state }
+    }
+
+    /// Create a layer drawing `image`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory.
+    pub fn with_image(frame: Rect, image: DrawCommandImage) -> PdcLayer {
+        PdcLayer::new(frame, Content::Image(image))
+    }
+
+    /// Create a layer showing the first frame of `sequence`, until
+    /// [`play`](PdcLayer::play) is called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory.
+    pub fn with_sequence(frame: Rect, sequence: DrawCommandSequence) -> PdcLayer {
+        PdcLayer::new(frame, Content::Sequence(sequence))
+    }
+
+    pub fn layer(&self) -> &Layer {
+        unsafe { Layer::from_ptr(self.raw) }
+    }
+
+    /// Set where the top left corner of the image is drawn, relative to the
+    /// layer's bounds.
+    pub fn set_offset(&self, offset: Point) {
+        self.state.offset.set(offset);
+        self.layer().mark_dirty();
+    }
+
+    /// Borrow the image, if the layer was created with one. Call
+    /// [`Layer::mark_dirty`] after changing it.
+    pub fn image_mut(&self) -> Option<RefMut<'_, DrawCommandImage>> {
+        RefMut::filter_map(self.state.content.borrow_mut(), |content| match content {
+            Content::Image(image) => Some(image),
+            Content::Sequence(_) => None,
+        })
+        .ok()
+    }
+
+    /// Borrow the sequence, if the layer was created with one. Call
+    /// [`Layer::mark_dirty`] after changing it.
+    pub fn sequence_mut(&self) -> Option<RefMut<'_, DrawCommandSequence>> {
+        RefMut::filter_map(self.state.content.borrow_mut(), |content| match content {
+            Content::Sequence(sequence) => Some(sequence),
+            Content::Image(_) => None,
+        })
+        .ok()
+    }
+
+    /// Start or resume playing the sequence. Does nothing for images, or
+    /// once the sequence has played `play_count` times.
+    pub fn play(&self) {
+        if self.is_playing() || self.state.is_finished() {
+            return;
+        }
+        let state = &*self.state as *const State;
+        let raw = self.raw;
+        let interval = Interval::every(FRAME_INTERVAL_MS, move || {
+            // The interval is owned by the state, so the state outlives it.
+            let state = unsafe { &*state };
+            state
+                .elapsed_ms
+                .set(state.elapsed_ms.get().saturating_add(FRAME_INTERVAL_MS));
+            unsafe { layer_mark_dirty(raw) };
+            if state.is_finished() {
+                state.playback.borrow_mut().take();
+            }
+        });
+        *self.state.playback.borrow_mut() = Some(interval);
+    }
+
+    /// Stop playing, keeping the current frame on screen.
+    pub fn pause(&self) {
+        self.state.playback.borrow_mut().take();
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.state.playback.borrow().is_some()
+    }
+
+    /// Go back to the first frame, without changing whether the sequence is
+    /// playing.
+    pub fn rewind(&self) {
+        self.state.elapsed_ms.set(0);
+        self.layer().mark_dirty();
+    }
+
+    /// Milliseconds of the sequence played so far, across every play.
+    pub fn elapsed(&self) -> u32 {
+        self.state.elapsed_ms.get()
+    }
+}
+
+impl AsRef<Layer> for PdcLayer {
+    fn as_ref(&self) -> &Layer {
+        self.layer()
+    }
+}
+
+impl Drop for PdcLayer {
+    fn drop(&mut self) {
+        self.pause();
+        unsafe { layer_destroy(self.raw) }
+    }
+}
@@ -0,0 +1,435 @@
+//! Scrolling content that is larger than the screen.
+
+use core::cell::{Cell, RefCell};
+use core::ffi::c_void;
+use core::mem;
+use core::ops::Deref;
+use core::ptr::null;
+
+use rust_alloc::boxed::Box;
+use rust_alloc::rc::Rc;
+use rust_alloc::vec::Vec;
+
+use pebblesdk_sys::{
+    content_indicator_configure_direction, content_indicator_create, content_indicator_destroy,
+    content_indicator_get_content_available, content_indicator_set_content_available, layer_create,
+    layer_destroy, layer_remove_from_parent, scroll_layer_add_child, scroll_layer_create,
+    scroll_layer_destroy, scroll_layer_get_content_indicator, scroll_layer_get_content_offset,
+    scroll_layer_get_content_size, scroll_layer_get_layer, scroll_layer_get_paging,
+    scroll_layer_get_shadow_hidden, scroll_layer_set_callbacks,
+    scroll_layer_set_click_config_onto_window, scroll_layer_set_content_offset,
+    scroll_layer_set_content_size, scroll_layer_set_context, scroll_layer_set_paging,
+    scroll_layer_set_shadow_hidden, ContentIndicator as RawContentIndicator,
+    ContentIndicatorConfig as RawContentIndicatorConfig,
+    ContentIndicatorConfig__bindgen_ty_1 as RawContentIndicatorColors,
+    ContentIndicatorDirection as RawContentIndicatorDirection, ContentIndicatorDirectionDown,
+    ContentIndicatorDirectionUp, Layer as RawLayer, ScrollLayer as RawScrollLayer,
+    ScrollLayerCallbacks,
+};
+
+use crate::click::{subscribe, ClickConfig, SharedClickConfig};
+use crate::graphics::{Align, Color, Point, Rect, Size};
+use crate::layer::Layer;
+use crate::timer::defer;
+use crate::window::Window;
+
+/// The height of the content indicators added on round displays.
+const INDICATOR_HEIGHT: i16 = 16;
+
+/// Which edge of the content a [`ContentIndicator`] arrow points past.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ContentIndicatorDirection {
+    Up,
+    Down,
+}
+
+impl ContentIndicatorDirection {
+    fn raw(self) -> RawContentIndicatorDirection {
+        match self {
+            ContentIndicatorDirection::Up => ContentIndicatorDirectionUp,
+            ContentIndicatorDirection::Down => ContentIndicatorDirectionDown,
+        }
+    }
+}
+
+/// How to draw one direction of a [`ContentIndicator`].
+#[derive(Clone, Copy)]
+pub struct ContentIndicatorConfig<'a> {
+    /// The layer the arrow is drawn in. Its update proc is replaced while the
+    /// arrow is shown, and it must outlive the content indicator.
+    pub layer: &'a Layer,
+    /// Hide the arrow again after a short time.
+    pub times_out: bool,
+    pub alignment: Align,
+    pub foreground: Color,
+    pub background: Color,
+}
+
+/// Arrows shown when there is more content above or below what is on screen.
+///
+/// Only ever handled by reference, borrowed from a [`ScrollLayer`] or an
+/// [`OwnedContentIndicator`].
+#[repr(transparent)]
+pub struct ContentIndicator(RawContentIndicator);
+
+impl ContentIndicator {
+    /// # Safety
+    ///
+    /// `raw` must point to a valid content indicator that outlives the
+    /// lifetime `'a`.
+    unsafe fn from_ptr<'a>(raw: *mut RawContentIndicator) -> &'a ContentIndicator {
+        unsafe { &*(raw as *const ContentIndicator) }
+    }
+
+    /// Get the underlying PebbleOS content indicator, for use with
+    /// [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut RawContentIndicator {
+        self as *const ContentIndicator as *mut RawContentIndicator
+    }
+
+    /// Set how the arrow for `direction` is drawn, or stop drawing it if
+    /// `config` is `None`. Returns false if the layer is already used by
+    /// another direction or indicator.
+    pub fn configure_direction(
+        &self,
+        direction: ContentIndicatorDirection,
+        config: Option<&ContentIndicatorConfig<'_>>,
+    ) -> bool {
+        let config = config.map(|config| RawContentIndicatorConfig {
+            layer: config.layer.as_ptr(),
+            times_out: config.times_out,
+            alignment: config.alignment.raw(),
+            colors: RawContentIndicatorColors {
+                foreground: config.foreground.raw(),
+                background: config.background.raw(),
+            },
+        });
+        unsafe {
+            content_indicator_configure_direction(
+                self.as_ptr(),
+                direction.raw(),
+                config.as_ref().map_or(null(), |config| config),
+            )
+        }
+    }
+
+    pub fn content_available(&self, direction: ContentIndicatorDirection) -> bool {
+        unsafe { content_indicator_get_content_available(self.as_ptr(), direction.raw()) }
+    }
+
+    /// Show or hide the arrow for `direction`. Scroll layers do this
+    /// automatically for their own content indicator.
+    pub fn set_content_available(&self, direction: ContentIndicatorDirection, available: bool) {
+        unsafe {
+            content_indicator_set_content_available(self.as_ptr(), direction.raw(), available)
+        }
+    }
+}
+
+/// A standalone [`ContentIndicator`], destroyed when dropped.
+pub struct OwnedContentIndicator {
+    raw: *mut RawContentIndicator,
+}
+
+impl OwnedContentIndicator {
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory.
+    pub fn new() -> OwnedContentIndicator {
+        let raw = unsafe { content_indicator_create() };
+        assert!(!raw.is_null(), "Failed to create content indicator");
+        OwnedContentIndicator { raw }
+    }
+}
+
+impl Default for OwnedContentIndicator {
+    fn default() -> OwnedContentIndicator {
+        OwnedContentIndicator::new()
+    }
+}
+
+impl Deref for OwnedContentIndicator {
+    type Target = ContentIndicator;
+
+    fn deref(&self) -> &ContentIndicator {
+        unsafe { ContentIndicator::from_ptr(self.raw) }
+    }
+}
+
+impl Drop for OwnedContentIndicator {
+    fn drop(&mut self) {
+        unsafe { content_indicator_destroy(self.raw) }
+    }
+}
+
+type OffsetChangedHandler = Box<dyn FnMut(Point)>;
+
+#[derive(Default)]
+struct Callbacks {
+    offset_changed: Cell<Option<OffsetChangedHandler>>,
+    click_config: RefCell<Option<SharedClickConfig>>,
+    // The number of callbacks running.
+    dispatching: Cell<u32>,
+}
+
+impl Callbacks {
+    /// Hold a reference to the callbacks at `context` while `f` runs, and
+    /// count it as dispatching so that dropping the layer from `f` is
+    /// deferred. See `ScrollLayer::drop`.
+    fn dispatch<F: FnOnce(&Callbacks)>(context: *mut c_void, f: F) {
+        let callbacks = unsafe {
+            Rc::increment_strong_count(context as *const Callbacks);
+            Rc::from_raw(context as *const Callbacks)
+        };
+        callbacks.dispatching.set(callbacks.dispatching.get() + 1);
+        f(&callbacks);
+        callbacks.dispatching.set(callbacks.dispatching.get() - 1);
+    }
+}
+
+unsafe extern "C" fn click_config_provider(context: *mut c_void) {
+    Callbacks::dispatch(context, |callbacks| {
+        if let Some(config) = &*callbacks.click_config.borrow() {
+            unsafe { subscribe(config.context()) };
+        }
+    });
+}
+
+unsafe extern "C" fn content_offset_changed(
+    scroll_layer: *mut RawScrollLayer,
+    context: *mut c_void,
+) {
+    let offset = unsafe { scroll_layer_get_content_offset(scroll_layer) }.into();
+    Callbacks::dispatch(context, |callbacks| {
+        if let Some(mut handler) = callbacks.offset_changed.take() {
+            handler(offset);
+            // Put the handler back, unless it was replaced while running.
+            let replacement = callbacks.offset_changed.take();
+            callbacks.offset_changed.set(replacement.or(Some(handler)));
+        }
+    });
+}
+
+/// A layer that scrolls its children with the up and down buttons.
+///
+/// On round displays, the scroll layer pages a screen at a time and shows
+/// arrows at the top and bottom when there is more content in that direction.
+///
+/// ```ignore
+/// let mut scroll = ScrollLayer::new(window.root_layer().bounds());
+/// scroll.set_content_size(Size::new(144, 400));
+/// scroll.add_owned_child(text);
+/// scroll.set_click_config_onto_window(&window);
+/// window.root_layer().add_child(scroll.layer());
+/// ```
+pub struct ScrollLayer {
+    raw: *mut RawScrollLayer,
+    callbacks: Rc<Callbacks>,
+    children: Vec<Box<dyn AsRef<Layer>>>,
+    indicator_layers: Option<[*mut RawLayer; 2]>,
+}
+
+impl ScrollLayer {
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory.
+    pub fn new(frame: Rect) -> ScrollLayer {
+        let raw = unsafe { scroll_layer_create(frame.into()) };
+        assert!(!raw.is_null(), "Failed to create scroll layer");
+        let callbacks = Rc::new(Callbacks::default());
+        unsafe {
+            scroll_layer_set_context(raw, Rc::as_ptr(&callbacks) as *mut c_void);
+            scroll_layer_set_callbacks(
+                raw,
+                ScrollLayerCallbacks {
+                    click_config_provider: Some(click_config_provider),
+                    content_offset_changed_handler: Some(content_offset_changed),
+                },
+            );
+        }
+        let mut scroll = ScrollLayer {
+            raw,
+            callbacks,
+            children: Vec::new(),
+            indicator_layers: None,
+        };
+        if cfg!(pebble_sdk_platform = "chalk") {
+            scroll.set_paging(true);
+            scroll.add_indicators(frame.size);
+        }
+        scroll
+    }
+
+    // Add arrows along the top and bottom edges. They are children of the
+    // scroll layer itself rather than its content, so they don't scroll.
+    fn add_indicators(&mut self, size: Size) {
+        let frames = [
+            Rect::new(0, 0, size.w, INDICATOR_HEIGHT),
+            Rect::new(0, size.h - INDICATOR_HEIGHT, size.w, INDICATOR_HEIGHT),
+        ];
+        let layers = frames.map(|frame| {
+            let layer = unsafe { layer_create(frame.into()) };
+            assert!(!layer.is_null(), "Failed to create layer");
+            layer
+        });
+        self.indicator_layers = Some(layers);
+        let directions = [
+            ContentIndicatorDirection::Up,
+            ContentIndicatorDirection::Down,
+        ];
+        for (direction, layer) in directions.into_iter().zip(layers) {
+            let layer = unsafe { Layer::from_ptr(layer) };
+            self.layer().add_child(layer);
+            self.content_indicator().configure_direction(
+                direction,
+                Some(&ContentIndicatorConfig {
+                    layer,
+                    times_out: false,
+                    alignment: Align::Center,
+                    foreground: Color::BLACK,
+                    background: Color::WHITE,
+                }),
+            );
+        }
+    }
+
+    pub fn layer(&self) -> &Layer {
+        unsafe { Layer::from_ptr(scroll_layer_get_layer(self.raw)) }
+    }
+
+    /// Get the underlying PebbleOS scroll layer, for use with [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut RawScrollLayer {
+        self.raw
+    }
+
+    /// Add a layer to the scrolling content. The child must outlive the
+    /// scroll layer, or be removed from it before being destroyed.
+    pub fn add_child(&self, child: &Layer) {
+        unsafe { scroll_layer_add_child(self.raw, child.as_ptr()) }
+    }
+
+    /// Add a layer to the scrolling content, keeping it alive until the
+    /// scroll layer is dropped.
+    pub fn add_owned_child<L: AsRef<Layer> + 'static>(&mut self, child: L) {
+        self.add_child(child.as_ref());
+        self.children.push(Box::new(child));
+    }
+
+    /// Let the scroll layer handle the up and down buttons of `window`,
+    /// replacing the window's click configuration.
+    pub fn set_click_config_onto_window(&self, window: &Window) {
+        unsafe { scroll_layer_set_click_config_onto_window(self.raw, window.as_ptr()) }
+    }
+
+    /// Like [`set_click_config_onto_window`](ScrollLayer::set_click_config_onto_window),
+    /// but also subscribing to the handlers in `config`. Handlers for the up
+    /// and down buttons replace scrolling.
+    pub fn set_click_config_onto_window_with(&self, window: &Window, config: ClickConfig) {
+        let config = SharedClickConfig::new(config);
+        let old = self.callbacks.click_config.replace(Some(config));
+        self.set_click_config_onto_window(window);
+        // Only release the old config after PebbleOS has let go of it.
+        drop(old);
+    }
+
+    /// Call `handler` with the new content offset whenever the content
+    /// scrolls.
+    pub fn set_content_offset_changed_handler<F>(&self, handler: F)
+    where
+        F: FnMut(Point) + 'static,
+    {
+        self.callbacks.offset_changed.set(Some(Box::new(handler)));
+    }
+
+    /// The size of the scrolling content. Scrolling stops at its edges.
+    pub fn content_size(&self) -> Size {
+        unsafe { scroll_layer_get_content_size(self.raw) }.into()
+    }
+
+    pub fn set_content_size(&self, size: Size) {
+        unsafe { scroll_layer_set_content_size(self.raw, size.into()) }
+    }
+
+    /// The position of the content relative to the top left of the layer.
+    /// Scrolling down moves the content up, making the offset negative.
+    pub fn content_offset(&self) -> Point {
+        unsafe { scroll_layer_get_content_offset(self.raw) }.into()
+    }
+
+    pub fn set_content_offset(&self, offset: Point, animated: bool) {
+        unsafe { scroll_layer_set_content_offset(self.raw, offset.into(), animated) }
+    }
+
+    pub fn is_shadow_hidden(&self) -> bool {
+        unsafe { scroll_layer_get_shadow_hidden(self.raw) }
+    }
+
+    /// Show or hide the shadows drawn at the top and bottom of the layer on
+    /// rectangular displays.
+    pub fn set_shadow_hidden(&self, hidden: bool) {
+        unsafe { scroll_layer_set_shadow_hidden(self.raw, hidden) }
+    }
+
+    pub fn is_paging(&self) -> bool {
+        unsafe { scroll_layer_get_paging(self.raw) }
+    }
+
+    /// Scroll a full page at a time instead of a few lines. Enabled by
+    /// default on round displays.
+    pub fn set_paging(&self, paging: bool) {
+        unsafe { scroll_layer_set_paging(self.raw, paging) }
+    }
+
+    /// The arrows shown when there is more content to scroll to. Only drawn
+    /// once configured, which happens automatically on round displays.
+    pub fn content_indicator(&self) -> &ContentIndicator {
+        unsafe { ContentIndicator::from_ptr(scroll_layer_get_content_indicator(self.raw)) }
+    }
+}
+
+impl AsRef<Layer> for ScrollLayer {
+    fn as_ref(&self) -> &Layer {
+        self.layer()
+    }
+}
+
+impl Drop for ScrollLayer {
+    fn drop(&mut self) {
+        let (raw, indicator_layers) = (self.raw, self.indicator_layers);
+        let children = mem::take(&mut self.children);
+        if self.callbacks.dispatching.get() == 0 {
+            destroy(raw, children, indicator_layers);
+            return;
+        }
+        // Dropped from one of its own callbacks, while PebbleOS is still
+        // scrolling or configuring clicks. Take the layer off screen now, and
+        // destroy it once control returns to the event loop.
+        unsafe { layer_remove_from_parent(scroll_layer_get_layer(raw)) };
+        let callbacks = self.callbacks.clone();
+        defer(move || {
+            destroy(raw, children, indicator_layers);
+            drop(callbacks);
+        });
+    }
+}
+
+fn destroy(
+    raw: *mut RawScrollLayer,
+    children: Vec<Box<dyn AsRef<Layer>>>,
+    indicator_layers: Option<[*mut RawLayer; 2]>,
+) {
+    drop(children);
+    // The indicator layers are still used by the content indicator until the
+    // scroll layer is destroyed, but must be removed from it first.
+    if let Some(layers) = indicator_layers {
+        for layer in layers {
+            unsafe { layer_remove_from_parent(layer) };
+        }
+    }
+    unsafe { scroll_layer_destroy(raw) };
+    if let Some(layers) = indicator_layers {
+        for layer in layers {
+            unsafe { layer_destroy(layer) };
+        }
+    }
+}
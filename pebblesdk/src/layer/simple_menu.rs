@@ -0,0 +1,271 @@
+//! Menus with fixed contents, built up from sections of items.
+
+use core::cell::{Cell, RefCell};
+use core::ffi::{c_int, c_void};
+use core::ptr::{null, null_mut};
+
+use rust_alloc::boxed::Box;
+use rust_alloc::ffi::CString;
+use rust_alloc::rc::Rc;
+use rust_alloc::vec::Vec;
+
+use pebblesdk_sys::{
+    layer_remove_from_parent, menu_layer_get_selected_index, menu_layer_set_selected_index,
+    simple_menu_layer_create, simple_menu_layer_destroy, simple_menu_layer_get_layer,
+    simple_menu_layer_get_menu_layer, SimpleMenuItem as RawSimpleMenuItem,
+    SimpleMenuLayer as RawSimpleMenuLayer, SimpleMenuSection as RawSimpleMenuSection,
+};
+
+use crate::graphics::{Bitmap, Rect};
+use crate::layer::menu::{MenuIndex, MenuRowAlign};
+use crate::layer::Layer;
+use crate::timer::defer;
+use crate::window::Window;
+
+fn c_string(text: &str) -> CString {
+    CString::new(text).expect("Menu text contains a nul byte")
+}
+
+struct Item {
+    title: CString,
+    subtitle: Option<CString>,
+    icon: Option<Rc<Bitmap>>,
+    callback: RefCell<Box<dyn FnMut()>>,
+}
+
+/// A section of a [`SimpleMenuLayer`], passed to the closure given to
+/// [`SimpleMenuBuilder::section`].
+pub struct SimpleMenuSection {
+    title: Option<CString>,
+    items: Vec<Item>,
+}
+
+impl SimpleMenuSection {
+    /// Add a row with a title and optional icon, calling `callback` when it is
+    /// selected.
+    pub fn item<F>(self, title: &str, icon: Option<Rc<Bitmap>>, callback: F) -> SimpleMenuSection
+    where
+        F: FnMut() + 'static,
+    {
+        self.push(title, None, icon, callback)
+    }
+
+    /// Add a row with a title, subtitle, and optional icon, calling
+    /// `callback` when it is selected.
+    pub fn item_with_subtitle<F>(
+        self,
+        title: &str,
+        subtitle: &str,
+        icon: Option<Rc<Bitmap>>,
+        callback: F,
+    ) -> SimpleMenuSection
+    where
+        F: FnMut() + 'static,
+    {
+        self.push(title, Some(subtitle), icon, callback)
+    }
+
+    fn push<F>(
+        mut self,
+        title: &str,
+        subtitle: Option<&str>,
+        icon: Option<Rc<Bitmap>>,
+        callback: F,
+    ) -> SimpleMenuSection
+    where
+        F: FnMut() + 'static,
+    {
+        self.items.push(Item {
+            title: c_string(title),
+            subtitle: subtitle.map(c_string),
+            icon,
+            callback: RefCell::new(Box::new(callback)),
+        });
+        self
+    }
+}
+
+/// Builds a [`SimpleMenuLayer`] one section at a time.
+///
+/// ```ignore
+/// let menu = SimpleMenuLayer::builder()
+///     .section("Units", |s| {
+///         s.item("Metric", None, || set_units(Units::Metric))
+///             .item("Imperial", None, || set_units(Units::Imperial))
+///     })
+///     .build(window.root_layer().bounds(), &window);
+/// window.root_layer().add_child(menu.layer());
+/// ```
+#[derive(Default)]
+pub struct SimpleMenuBuilder {
+    sections: Vec<SimpleMenuSection>,
+}
+
+impl SimpleMenuBuilder {
+    /// Add a section with the items added by `items`. An empty `title` leaves
+    /// the section without a header.
+    pub fn section<F>(mut self, title: &str, items: F) -> SimpleMenuBuilder
+    where
+        F: FnOnce(SimpleMenuSection) -> SimpleMenuSection,
+    {
+        let section = SimpleMenuSection {
+            title: (!title.is_empty()).then(|| c_string(title)),
+            items: Vec::new(),
+        };
+        self.sections.push(items(section));
+        self
+    }
+
+    /// Create the menu layer, letting it handle the up, down, and select
+    /// buttons of `window`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory.
+    pub fn build(self, frame: Rect, window: &Window) -> SimpleMenuLayer {
+        let raw_items: Box<[Box<[RawSimpleMenuItem]>]> = self
+            .sections
+            .iter()
+            .map(|section| {
+                section
+                    .items
+                    .iter()
+                    .map(|item| RawSimpleMenuItem {
+                        title: item.title.as_ptr(),
+                        subtitle: item.subtitle.as_deref().map_or(null(), |s| s.as_ptr()),
+                        icon: item.icon.as_deref().map_or(null_mut(), Bitmap::as_ptr),
+                        callback: Some(select_trampoline),
+                    })
+                    .collect()
+            })
+            .collect();
+        let raw_sections = self
+            .sections
+            .iter()
+            .zip(raw_items.iter())
+            .map(|(section, items)| RawSimpleMenuSection {
+                title: section.title.as_deref().map_or(null(), |s| s.as_ptr()),
+                items: items.as_ptr(),
+                num_items: items.len() as u32,
+            })
+            .collect();
+        let menu = Rc::new(Menu {
+            raw: Cell::new(null_mut()),
+            dispatching: Cell::new(0),
+            sections: self.sections.into_boxed_slice(),
+            raw_sections,
+            raw_items,
+        });
+        let raw = unsafe {
+            simple_menu_layer_create(
+                frame.into(),
+                window.as_ptr(),
+                menu.raw_sections.as_ptr(),
+                menu.raw_sections.len() as i32,
+                Rc::as_ptr(&menu) as *mut c_void,
+            )
+        };
+        assert!(!raw.is_null(), "Failed to create simple menu layer");
+        menu.raw.set(raw);
+        SimpleMenuLayer { menu }
+    }
+}
+
+// Everything PebbleOS points into while the layer exists. Item callbacks only
+// learn the row that was selected, so the section comes from the layer itself.
+struct Menu {
+    raw: Cell<*mut RawSimpleMenuLayer>,
+    // The number of item callbacks running.
+    dispatching: Cell<u32>,
+    sections: Box<[SimpleMenuSection]>,
+    raw_sections: Box<[RawSimpleMenuSection]>,
+    // Never read, but pointed to by `raw_sections`.
+    #[allow(dead_code)]
+    raw_items: Box<[Box<[RawSimpleMenuItem]>]>,
+}
+
+unsafe extern "C" fn select_trampoline(row: c_int, context: *mut c_void) {
+    // Hold a reference for the duration of the callback, in case it drops the
+    // layer. The layer itself is destroyed later, see `SimpleMenuLayer::drop`.
+    let menu = unsafe {
+        Rc::increment_strong_count(context as *const Menu);
+        Rc::from_raw(context as *const Menu)
+    };
+    let section = unsafe {
+        menu_layer_get_selected_index(simple_menu_layer_get_menu_layer(menu.raw.get())).section
+    };
+    let item = menu
+        .sections
+        .get(section as usize)
+        .and_then(|section| section.items.get(row as usize));
+    if let Some(item) = item {
+        if let Ok(mut callback) = item.callback.try_borrow_mut() {
+            menu.dispatching.set(menu.dispatching.get() + 1);
+            callback();
+            menu.dispatching.set(menu.dispatching.get() - 1);
+        }
+    }
+}
+
+/// A menu of titled items with optional subtitles and icons, each calling a
+/// closure when selected.
+pub struct SimpleMenuLayer {
+    menu: Rc<Menu>,
+}
+
+impl SimpleMenuLayer {
+    pub fn builder() -> SimpleMenuBuilder {
+        SimpleMenuBuilder::default()
+    }
+
+    pub fn layer(&self) -> &Layer {
+        unsafe { Layer::from_ptr(simple_menu_layer_get_layer(self.as_ptr())) }
+    }
+
+    /// Get the underlying PebbleOS simple menu layer, for use with
+    /// [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut RawSimpleMenuLayer {
+        self.menu.raw.get()
+    }
+
+    pub fn selected_index(&self) -> MenuIndex {
+        unsafe { menu_layer_get_selected_index(simple_menu_layer_get_menu_layer(self.as_ptr())) }
+            .into()
+    }
+
+    pub fn set_selected_index(&self, index: MenuIndex, animated: bool) {
+        unsafe {
+            menu_layer_set_selected_index(
+                simple_menu_layer_get_menu_layer(self.as_ptr()),
+                index.into(),
+                MenuRowAlign::Center.raw(),
+                animated,
+            )
+        }
+    }
+}
+
+impl AsRef<Layer> for SimpleMenuLayer {
+    fn as_ref(&self) -> &Layer {
+        self.layer()
+    }
+}
+
+impl Drop for SimpleMenuLayer {
+    fn drop(&mut self) {
+        let raw = self.as_ptr();
+        if self.menu.dispatching.get() == 0 {
+            unsafe { simple_menu_layer_destroy(raw) };
+            return;
+        }
+        // Dropped from an item callback, while PebbleOS is still handling the
+        // select. Take the layer off screen now, and destroy it once control
+        // returns to the event loop.
+        unsafe { layer_remove_from_parent(simple_menu_layer_get_layer(raw)) };
+        let menu = self.menu.clone();
+        defer(move || {
+            unsafe { simple_menu_layer_destroy(raw) };
+            drop(menu);
+        });
+    }
+}
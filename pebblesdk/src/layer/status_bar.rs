@@ -0,0 +1,118 @@
+//! The bar showing the time at the top of a window.
+
+use pebblesdk_sys::{
+    status_bar_layer_create, status_bar_layer_destroy, status_bar_layer_get_background_color,
+    status_bar_layer_get_foreground_color, status_bar_layer_get_layer, status_bar_layer_set_colors,
+    status_bar_layer_set_separator_mode, StatusBarLayer as RawStatusBarLayer,
+    StatusBarLayerSeparatorMode as RawSeparatorMode, StatusBarLayerSeparatorModeDotted,
+    StatusBarLayerSeparatorModeNone,
+};
+
+use crate::graphics::{Color, Rect};
+use crate::layer::Layer;
+use crate::window::Window;
+
+/// The height of a status bar.
+#[cfg(not(pebble_sdk_platform = "chalk"))]
+pub const STATUS_BAR_LAYER_HEIGHT: i16 = 16;
+/// The height of a status bar.
+#[cfg(pebble_sdk_platform = "chalk")]
+pub const STATUS_BAR_LAYER_HEIGHT: i16 = 24;
+
+/// The line drawn along the bottom of a status bar.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SeparatorMode {
+    #[default]
+    None,
+    Dotted,
+}
+
+impl SeparatorMode {
+    fn raw(self) -> RawSeparatorMode {
+        match self {
+            SeparatorMode::None => StatusBarLayerSeparatorModeNone,
+            SeparatorMode::Dotted => StatusBarLayerSeparatorModeDotted,
+        }
+    }
+}
+
+/// A status bar, created along the top edge of its parent.
+///
+/// ```ignore
+/// let status_bar = StatusBarLayer::new();
+/// status_bar.set_separator_mode(SeparatorMode::Dotted);
+/// status_bar.add_to_window(&window);
+/// let menu = MenuLayer::new(StatusBarLayer::content_bounds(&window), data);
+/// ```
+pub struct StatusBarLayer {
+    raw: *mut RawStatusBarLayer,
+}
+
+impl StatusBarLayer {
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory.
+    pub fn new() -> StatusBarLayer {
+        let raw = unsafe { status_bar_layer_create() };
+        assert!(!raw.is_null(), "Failed to create status bar layer");
+        StatusBarLayer { raw }
+    }
+
+    pub fn layer(&self) -> &Layer {
+        unsafe { Layer::from_ptr(status_bar_layer_get_layer(self.raw)) }
+    }
+
+    /// Get the underlying PebbleOS status bar layer, for use with
+    /// [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut RawStatusBarLayer {
+        self.raw
+    }
+
+    /// Add the status bar to the top of `window`'s root layer.
+    pub fn add_to_window(&self, window: &Window) {
+        window.root_layer().add_child(self.layer());
+    }
+
+    /// The area of `window` left below a status bar, excluding anything
+    /// covering the bottom of the window, such as a Timeline Quick View.
+    pub fn content_bounds(window: &Window) -> Rect {
+        let mut bounds = window.root_layer().unobstructed_bounds();
+        bounds.origin.y += STATUS_BAR_LAYER_HEIGHT;
+        bounds.size.h -= STATUS_BAR_LAYER_HEIGHT;
+        bounds
+    }
+
+    pub fn background_color(&self) -> Color {
+        Color::from_raw(unsafe { status_bar_layer_get_background_color(self.raw) })
+    }
+
+    pub fn foreground_color(&self) -> Color {
+        Color::from_raw(unsafe { status_bar_layer_get_foreground_color(self.raw) })
+    }
+
+    pub fn set_colors(&self, background: Color, foreground: Color) {
+        unsafe { status_bar_layer_set_colors(self.raw, background.raw(), foreground.raw()) }
+    }
+
+    pub fn set_separator_mode(&self, mode: SeparatorMode) {
+        unsafe { status_bar_layer_set_separator_mode(self.raw, mode.raw()) }
+    }
+}
+
+impl Default for StatusBarLayer {
+    fn default() -> StatusBarLayer {
+        StatusBarLayer::new()
+    }
+}
+
+impl AsRef<Layer> for StatusBarLayer {
+    fn as_ref(&self) -> &Layer {
+        self.layer()
+    }
+}
+
+impl Drop for StatusBarLayer {
+    fn drop(&mut self) {
+        unsafe { status_bar_layer_destroy(self.raw) }
+    }
+}
@@ -1,6 +1,19 @@
 #![no_std]
 
+extern crate alloc as rust_alloc;
+
 pub mod alloc;
+pub mod animation;
+pub mod app;
+pub mod cell;
+pub mod click;
+pub mod graphics;
+pub mod layer;
 pub mod panic;
+pub mod services;
+pub mod task;
+pub mod timer;
+pub mod trig;
+pub mod window;
 
 pub use pebblesdk_sys as sys;
@@ -0,0 +1,4 @@
+//! Wrappers around the PebbleOS event services.
+
+#[cfg(not(pebble_sdk_platform = "aplite"))]
+pub mod health;
@@ -0,0 +1,470 @@
+//! Access to the step, sleep, and heart rate data recorded by PebbleOS.
+//!
+//! Not available on aplite, which has no health tracking.
+
+use core::cell::{Cell, RefCell};
+use core::ffi::c_void;
+use core::mem::zeroed;
+use core::ops::{BitOr, BitOrAssign, Range};
+use core::ptr::null_mut;
+use core::slice;
+
+use rust_alloc::boxed::Box;
+
+use pebblesdk_sys::{
+    health_service_activities_iterate, health_service_events_subscribe,
+    health_service_events_unsubscribe, health_service_get_minute_history,
+    health_service_metric_accessible, health_service_peek_current_activities,
+    health_service_peek_current_value, health_service_sum, health_service_sum_today, time_t,
+    AmbientLightLevel as RawAmbientLightLevel, AmbientLightLevelDark, AmbientLightLevelLight,
+    AmbientLightLevelVeryDark, AmbientLightLevelVeryLight, HealthActivity as RawHealthActivity,
+    HealthActivityOpenWorkout, HealthActivityRestfulSleep, HealthActivityRun, HealthActivitySleep,
+    HealthActivityWalk, HealthEventHeartRateUpdate, HealthEventMetricAlert,
+    HealthEventMovementUpdate, HealthEventSignificantUpdate, HealthEventSleepUpdate,
+    HealthEventType, HealthIterationDirectionFuture, HealthIterationDirectionPast,
+    HealthMetric as RawHealthMetric, HealthMetricActiveKCalories, HealthMetricActiveSeconds,
+    HealthMetricHeartRateBPM, HealthMetricHeartRateRawBPM, HealthMetricRestingKCalories,
+    HealthMetricSleepRestfulSeconds, HealthMetricSleepSeconds, HealthMetricStepCount,
+    HealthMetricWalkedDistanceMeters, HealthMinuteData, HealthServiceAccessibilityMaskAvailable,
+    HealthServiceAccessibilityMaskNoPermission, HealthServiceAccessibilityMaskNotSupported,
+};
+
+pub use pebblesdk_sys::HealthValue;
+
+use crate::cell::AppCell;
+use crate::timer::defer;
+
+/// A quantity tracked by the health service.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HealthMetric {
+    StepCount,
+    ActiveSeconds,
+    WalkedDistanceMeters,
+    SleepSeconds,
+    SleepRestfulSeconds,
+    RestingKCalories,
+    ActiveKCalories,
+    HeartRateBPM,
+    HeartRateRawBPM,
+}
+
+impl HealthMetric {
+    fn raw(self) -> RawHealthMetric {
+        match self {
+            HealthMetric::StepCount => HealthMetricStepCount,
+            HealthMetric::ActiveSeconds => HealthMetricActiveSeconds,
+            HealthMetric::WalkedDistanceMeters => HealthMetricWalkedDistanceMeters,
+            HealthMetric::SleepSeconds => HealthMetricSleepSeconds,
+            HealthMetric::SleepRestfulSeconds => HealthMetricSleepRestfulSeconds,
+            HealthMetric::RestingKCalories => HealthMetricRestingKCalories,
+            HealthMetric::ActiveKCalories => HealthMetricActiveKCalories,
+            HealthMetric::HeartRateBPM => HealthMetricHeartRateBPM,
+            HealthMetric::HeartRateRawBPM => HealthMetricHeartRateRawBPM,
+        }
+    }
+}
+
+/// Get the total of a metric since midnight.
+pub fn sum_today(metric: HealthMetric) -> HealthValue {
+    unsafe { health_service_sum_today(metric.raw()) }
+}
+
+/// Get the total of a metric over a span of time.
+pub fn sum(metric: HealthMetric, time: Range<time_t>) -> HealthValue {
+    unsafe { health_service_sum(metric.raw(), time.start, time.end) }
+}
+
+/// Get the most recent value of a metric, such as the current heart rate.
+pub fn peek_current_value(metric: HealthMetric) -> HealthValue {
+    unsafe { health_service_peek_current_value(metric.raw()) }
+}
+
+/// Whether a metric can be read over a span of time, as reported by
+/// [`metric_accessible`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Accessibility {
+    Available,
+    NoPermission,
+    NotSupported,
+    NotAvailable,
+}
+
+/// Check if data for a metric can be read over a span of time.
+pub fn metric_accessible(metric: HealthMetric, time: Range<time_t>) -> Accessibility {
+    let mask = unsafe { health_service_metric_accessible(metric.raw(), time.start, time.end) };
+    if mask & HealthServiceAccessibilityMaskAvailable != 0 {
+        Accessibility::Available
+    } else if mask & HealthServiceAccessibilityMaskNoPermission != 0 {
+        Accessibility::NoPermission
+    } else if mask & HealthServiceAccessibilityMaskNotSupported != 0 {
+        Accessibility::NotSupported
+    } else {
+        // Any other mask, including an empty one, means the data can't be read.
+        Accessibility::NotAvailable
+    }
+}
+
+/// The ambient light level measured during a minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AmbientLightLevel {
+    Unknown,
+    VeryDark,
+    Dark,
+    Light,
+    VeryLight,
+}
+
+impl AmbientLightLevel {
+    fn from_raw(level: RawAmbientLightLevel) -> AmbientLightLevel {
+        #[allow(non_upper_case_globals)]
+        match level {
+            AmbientLightLevelVeryDark => AmbientLightLevel::VeryDark,
+            AmbientLightLevelDark => AmbientLightLevel::Dark,
+            AmbientLightLevelLight => AmbientLightLevel::Light,
+            AmbientLightLevelVeryLight => AmbientLightLevel::VeryLight,
+            _ => AmbientLightLevel::Unknown,
+        }
+    }
+}
+
+/// The health data recorded for a single minute.
+#[derive(Clone, Copy, Debug)]
+#[repr(transparent)]
+pub struct MinuteData(HealthMinuteData);
+
+impl MinuteData {
+    /// The number of steps taken.
+    pub fn steps(&self) -> u8 {
+        self.0.steps
+    }
+
+    /// The orientation of the watch, quantized by PebbleOS.
+    pub fn orientation(&self) -> u8 {
+        self.0.orientation
+    }
+
+    /// The vector magnitude count, a measure of overall movement.
+    pub fn vmc(&self) -> u16 {
+        self.0.vmc
+    }
+
+    /// The median heart rate, or 0 if it wasn't measured.
+    pub fn heart_rate_bpm(&self) -> u8 {
+        self.0.heart_rate_bpm
+    }
+
+    pub fn light(&self) -> AmbientLightLevel {
+        AmbientLightLevel::from_raw(self.0.light())
+    }
+
+    /// True if no data was recorded for this minute (e.g. the watch was off).
+    pub fn is_invalid(&self) -> bool {
+        self.0.is_invalid()
+    }
+}
+
+impl Default for MinuteData {
+    fn default() -> MinuteData {
+        // HealthMinuteData is plain old data, so all zeroes is a valid value.
+        MinuteData(unsafe { zeroed() })
+    }
+}
+
+/// Minute-by-minute health data, read with [`minute_history`].
+#[derive(Clone, Debug)]
+pub struct MinuteHistory<'a> {
+    records: slice::Iter<'a, MinuteData>,
+    time: Range<time_t>,
+    next_time: time_t,
+}
+
+impl MinuteHistory<'_> {
+    /// The span of time covered by the returned records. PebbleOS may adjust
+    /// this from the requested span to the nearest minutes it has data for.
+    pub fn time(&self) -> Range<time_t> {
+        self.time.clone()
+    }
+}
+
+impl<'a> Iterator for MinuteHistory<'a> {
+    /// The start of each minute paired with its data.
+    type Item = (time_t, &'a MinuteData);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = self.records.next()?;
+        let time = self.next_time;
+        self.next_time += 60;
+        Some((time, data))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.records.size_hint()
+    }
+}
+
+impl ExactSizeIterator for MinuteHistory<'_> {}
+
+/// Read the per-minute health data for a span of time into `buffer`, returning
+/// an iterator over the records PebbleOS filled in. At most `buffer.len()`
+/// minutes are read.
+pub fn minute_history(buffer: &mut [MinuteData], time: Range<time_t>) -> MinuteHistory<'_> {
+    let mut time_start = time.start;
+    let mut time_end = time.end;
+    let count = unsafe {
+        health_service_get_minute_history(
+            buffer.as_mut_ptr() as *mut HealthMinuteData,
+            buffer.len() as u32,
+            &raw mut time_start,
+            &raw mut time_end,
+        )
+    };
+    let count = (count as usize).min(buffer.len());
+
+    MinuteHistory {
+        records: buffer[..count].iter(),
+        time: time_start..time_end,
+        next_time: time_start,
+    }
+}
+
+/// A kind of activity detected by the health service.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HealthActivity {
+    Sleep,
+    RestfulSleep,
+    Walk,
+    Run,
+    OpenWorkout,
+}
+
+impl HealthActivity {
+    fn from_raw(activity: RawHealthActivity) -> Option<HealthActivity> {
+        #[allow(non_upper_case_globals)]
+        match activity {
+            HealthActivitySleep => Some(HealthActivity::Sleep),
+            HealthActivityRestfulSleep => Some(HealthActivity::RestfulSleep),
+            HealthActivityWalk => Some(HealthActivity::Walk),
+            HealthActivityRun => Some(HealthActivity::Run),
+            HealthActivityOpenWorkout => Some(HealthActivity::OpenWorkout),
+            _ => None,
+        }
+    }
+}
+
+/// A set of [`HealthActivity`] kinds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ActivityMask(u32);
+
+impl ActivityMask {
+    pub const NONE: ActivityMask = ActivityMask(0);
+    pub const ALL: ActivityMask = ActivityMask(
+        HealthActivitySleep
+            | HealthActivityRestfulSleep
+            | HealthActivityWalk
+            | HealthActivityRun
+            | HealthActivityOpenWorkout,
+    );
+
+    pub fn contains(self, activity: HealthActivity) -> bool {
+        self.0 & ActivityMask::from(activity).0 != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl From<HealthActivity> for ActivityMask {
+    fn from(activity: HealthActivity) -> ActivityMask {
+        ActivityMask(match activity {
+            HealthActivity::Sleep => HealthActivitySleep,
+            HealthActivity::RestfulSleep => HealthActivityRestfulSleep,
+            HealthActivity::Walk => HealthActivityWalk,
+            HealthActivity::Run => HealthActivityRun,
+            HealthActivity::OpenWorkout => HealthActivityOpenWorkout,
+        })
+    }
+}
+
+impl BitOr for ActivityMask {
+    type Output = ActivityMask;
+
+    fn bitor(self, rhs: ActivityMask) -> ActivityMask {
+        ActivityMask(self.0 | rhs.0)
+    }
+}
+
+impl BitOr<HealthActivity> for ActivityMask {
+    type Output = ActivityMask;
+
+    fn bitor(self, rhs: HealthActivity) -> ActivityMask {
+        self | ActivityMask::from(rhs)
+    }
+}
+
+impl BitOrAssign for ActivityMask {
+    fn bitor_assign(&mut self, rhs: ActivityMask) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Get the activities currently in progress.
+pub fn current_activities() -> ActivityMask {
+    ActivityMask(unsafe { health_service_peek_current_activities() })
+}
+
+/// The order to visit activities in with [`activities_iterate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IterationDirection {
+    /// Start from the most recent activity.
+    Past,
+    /// Start from the oldest activity.
+    Future,
+}
+
+unsafe extern "C" fn activities_iterate_trampoline<F>(
+    activity: RawHealthActivity,
+    time_start: time_t,
+    time_end: time_t,
+    context: *mut c_void,
+) -> bool
+where
+    F: FnMut(HealthActivity, Range<time_t>) -> bool,
+{
+    let callback = unsafe { &mut *(context as *mut F) };
+    match HealthActivity::from_raw(activity) {
+        Some(activity) => callback(activity, time_start..time_end),
+        None => true,
+    }
+}
+
+/// Call `callback` with each activity in `mask` that overlaps a span of time,
+/// stopping early if it returns false.
+pub fn activities_iterate<F>(
+    mask: ActivityMask,
+    time: Range<time_t>,
+    direction: IterationDirection,
+    mut callback: F,
+) where
+    F: FnMut(HealthActivity, Range<time_t>) -> bool,
+{
+    let direction = match direction {
+        IterationDirection::Past => HealthIterationDirectionPast,
+        IterationDirection::Future => HealthIterationDirectionFuture,
+    };
+
+    // The callback is only used for the duration of this (synchronous) call, so
+    // it's fine to lend out a pointer to it on the stack.
+    unsafe {
+        health_service_activities_iterate(
+            mask.0,
+            time.start,
+            time.end,
+            direction,
+            Some(activities_iterate_trampoline::<F>),
+            &raw mut callback as *mut c_void,
+        )
+    }
+}
+
+/// A notification that health data has changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HealthEvent {
+    /// Most or all metrics have changed, such as after midnight.
+    SignificantUpdate,
+    MovementUpdate,
+    SleepUpdate,
+    MetricAlert,
+    HeartRateUpdate,
+}
+
+impl HealthEvent {
+    fn from_raw(event: HealthEventType) -> Option<HealthEvent> {
+        #[allow(non_upper_case_globals)]
+        match event {
+            HealthEventSignificantUpdate => Some(HealthEvent::SignificantUpdate),
+            HealthEventMovementUpdate => Some(HealthEvent::MovementUpdate),
+            HealthEventSleepUpdate => Some(HealthEvent::SleepUpdate),
+            HealthEventMetricAlert => Some(HealthEvent::MetricAlert),
+            HealthEventHeartRateUpdate => Some(HealthEvent::HeartRateUpdate),
+            _ => None,
+        }
+    }
+}
+
+struct EventHandler {
+    // The number of calls to `callback` running.
+    dispatching: Cell<u32>,
+    callback: RefCell<Box<dyn FnMut(HealthEvent)>>,
+}
+
+/// The handler PebbleOS is currently calling, so an older subscription that
+/// has been replaced doesn't unsubscribe the newer one when dropped.
+static CURRENT_HANDLER: AppCell<*mut EventHandler> = AppCell::new(null_mut());
+
+/// An active subscription to health events, created by [`events_subscribe`].
+/// Unsubscribes when dropped, which can be done from inside the handler.
+pub struct HealthEventSubscription {
+    handler: *mut EventHandler,
+}
+
+impl Drop for HealthEventSubscription {
+    fn drop(&mut self) {
+        let handler = self.handler;
+        let current = CURRENT_HANDLER.with(|current| {
+            let is_current = *current == handler;
+            if is_current {
+                *current = null_mut();
+            }
+            is_current
+        });
+        if current {
+            unsafe { health_service_events_unsubscribe() };
+        }
+        if unsafe { (*handler).dispatching.get() } == 0 {
+            drop(unsafe { Box::from_raw(handler) });
+        } else {
+            // Dropped from inside the handler, which is still borrowed. Free it
+            // once control returns to the event loop.
+            defer(move || drop(unsafe { Box::from_raw(handler) }));
+        }
+    }
+}
+
+unsafe extern "C" fn events_trampoline(event: HealthEventType, context: *mut c_void) {
+    let handler = unsafe { &*(context as *const EventHandler) };
+    let Some(event) = HealthEvent::from_raw(event) else {
+        return;
+    };
+    // Skip the event if the handler is somehow already running, rather than
+    // panic inside a callback from PebbleOS.
+    if let Ok(mut callback) = handler.callback.try_borrow_mut() {
+        handler.dispatching.set(handler.dispatching.get() + 1);
+        callback(event);
+        handler.dispatching.set(handler.dispatching.get() - 1);
+    }
+}
+
+/// Call `handler` whenever health data changes. PebbleOS only supports one
+/// subscription at a time, so subscribing again replaces any previous
+/// subscription, whose handler is no longer called.
+///
+/// Returns `None` if the health service is unavailable.
+pub fn events_subscribe<F>(handler: F) -> Option<HealthEventSubscription>
+where
+    F: FnMut(HealthEvent) + 'static,
+{
+    let handler = Box::into_raw(Box::new(EventHandler {
+        dispatching: Cell::new(0),
+        callback: RefCell::new(Box::new(handler)),
+    }));
+    let subscribed =
+        unsafe { health_service_events_subscribe(Some(events_trampoline), handler as *mut c_void) };
+    if subscribed {
+        CURRENT_HANDLER.replace(handler);
+        Some(HealthEventSubscription { handler })
+    } else {
+        drop(unsafe { Box::from_raw(handler) });
+        None
+    }
+}
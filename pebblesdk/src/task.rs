@@ -0,0 +1,392 @@
+//! A minimal async executor driven by the app's event loop.
+//!
+//! Futures started with [`spawn`] are polled from inside `app_event_loop`.
+//! Waking a task schedules it to be polled with a zero-length `AppTimer`, so
+//! other events are still handled in between polls.
+
+use core::cell::{Cell, RefCell};
+use core::ffi::c_void;
+use core::future::Future;
+use core::pin::Pin;
+use core::ptr::null_mut;
+use core::task::{Context, Poll, Waker};
+
+use rust_alloc::rc::Rc;
+use rust_alloc::vec::Vec;
+
+use pebblesdk_sys::{
+    app_message_register_inbox_received, click_recognizer_get_button_id,
+    tick_timer_service_subscribe, tick_timer_service_unsubscribe, tm,
+    window_get_click_config_context, window_get_click_config_provider,
+    window_set_click_config_provider_with_context, window_single_click_subscribe,
+    window_stack_contains_window, window_stack_get_top_window, ClickConfigProvider,
+    ClickRecognizerRef, DictionaryIterator, TimeUnits, Window,
+};
+
+mod executor;
+mod message;
+
+pub use executor::spawn;
+pub use message::{Message, MessageIter, Value};
+
+use crate::cell::AppCell;
+use crate::click::ButtonId;
+use crate::timer::Timer;
+
+fn register_waker(wakers: &mut Vec<Waker>, waker: &Waker) {
+    if !wakers.iter().any(|w| w.will_wake(waker)) {
+        wakers.push(waker.clone());
+    }
+}
+
+struct SleepState {
+    done: Cell<bool>,
+    waker: RefCell<Option<Waker>>,
+}
+
+/// Future returned by [`sleep`].
+pub struct Sleep {
+    timeout_ms: u32,
+    timer: Option<(Timer, Rc<SleepState>)>,
+}
+
+/// Wait for `timeout_ms` milliseconds. The countdown starts when the future is
+/// first polled.
+pub fn sleep(timeout_ms: u32) -> Sleep {
+    Sleep {
+        timeout_ms,
+        timer: None,
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if let Some((_, state)) = &self.timer {
+            if state.done.get() {
+                return Poll::Ready(());
+            }
+            state.waker.replace(Some(cx.waker().clone()));
+            return Poll::Pending;
+        }
+
+        let state = Rc::new(SleepState {
+            done: Cell::new(false),
+            waker: RefCell::new(Some(cx.waker().clone())),
+        });
+        let timer_state = state.clone();
+        let timer = Timer::after(self.timeout_ms, move || {
+            timer_state.done.set(true);
+            if let Some(waker) = timer_state.waker.take() {
+                waker.wake();
+            }
+        });
+        self.timer = Some((timer, state));
+        Poll::Pending
+    }
+}
+
+/// The time reported by the tick timer service.
+#[derive(Clone, Copy, Debug)]
+pub struct Tick {
+    pub time: tm,
+    pub units_changed: TimeUnits,
+}
+
+struct TickWaiters {
+    units: TimeUnits,
+    generation: u32,
+    last: Option<Tick>,
+    wakers: Vec<Waker>,
+}
+
+static TICKS: AppCell<TickWaiters> = AppCell::new(TickWaiters {
+    units: 0,
+    generation: 0,
+    last: None,
+    wakers: Vec::new(),
+});
+
+unsafe extern "C" fn tick_handler(tick_time: *mut tm, units_changed: TimeUnits) {
+    let wakers = TICKS.with(|ticks| {
+        ticks.generation = ticks.generation.wrapping_add(1);
+        ticks.last = Some(Tick {
+            time: unsafe { *tick_time },
+            units_changed,
+        });
+        // Waiting futures resubscribe when they are next polled.
+        ticks.units = 0;
+        unsafe { tick_timer_service_unsubscribe() };
+        core::mem::take(&mut ticks.wakers)
+    });
+    wakers.into_iter().for_each(Waker::wake);
+}
+
+/// Future returned by [`next_tick`].
+pub struct NextTick {
+    units: TimeUnits,
+    generation: Option<u32>,
+}
+
+/// Wait for the next time any of `units` changes, such as the start of the next
+/// minute for `MINUTE_UNIT`.
+///
+/// This subscribes to the tick timer service while waiting, replacing any other
+/// tick handler the app has set.
+pub fn next_tick(units: TimeUnits) -> NextTick {
+    NextTick {
+        units,
+        generation: None,
+    }
+}
+
+impl Future for NextTick {
+    type Output = Tick;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Tick> {
+        let units = self.units;
+        let seen = self.generation;
+        let (ready, generation) = TICKS.with(|ticks| match (seen, ticks.last) {
+            (Some(seen), Some(tick))
+                if seen != ticks.generation && tick.units_changed & units != 0 =>
+            {
+                (Some(tick), ticks.generation)
+            }
+            _ => {
+                register_waker(&mut ticks.wakers, cx.waker());
+                if ticks.units | units != ticks.units {
+                    ticks.units |= units;
+                    unsafe { tick_timer_service_subscribe(ticks.units, Some(tick_handler)) };
+                }
+                (None, ticks.generation)
+            }
+        });
+        self.generation = Some(generation);
+        match ready {
+            Some(tick) => Poll::Ready(tick),
+            None => Poll::Pending,
+        }
+    }
+}
+
+struct Inbox {
+    installed: bool,
+    generation: u32,
+    last: Option<Message>,
+    wakers: Vec<Waker>,
+}
+
+static INBOX: AppCell<Inbox> = AppCell::new(Inbox {
+    installed: false,
+    generation: 0,
+    last: None,
+    wakers: Vec::new(),
+});
+
+unsafe extern "C" fn inbox_received(iterator: *mut DictionaryIterator, _context: *mut c_void) {
+    let message = unsafe {
+        let start = (*iterator).dictionary as *const u8;
+        let end = (*iterator).end as *const u8;
+        let length = end.offset_from(start).max(0) as usize;
+        Message {
+            buffer: core::slice::from_raw_parts(start, length).to_vec(),
+        }
+    };
+    let wakers = INBOX.with(|inbox| {
+        inbox.generation = inbox.generation.wrapping_add(1);
+        inbox.last = Some(message);
+        core::mem::take(&mut inbox.wakers)
+    });
+    wakers.into_iter().for_each(Waker::wake);
+}
+
+/// Future returned by [`next_message`].
+pub struct NextMessage {
+    generation: Option<u32>,
+}
+
+/// Wait for the next message received over AppMessage. If several messages
+/// arrive before the task is polled again, only the newest is returned.
+///
+/// This installs its own inbox handler, replacing any other the app has set.
+/// The app is still responsible for calling `app_message_open`.
+pub fn next_message() -> NextMessage {
+    NextMessage { generation: None }
+}
+
+impl Future for NextMessage {
+    type Output = Message;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Message> {
+        let seen = self.generation;
+        let (ready, generation) = INBOX.with(|inbox| match (seen, &inbox.last) {
+            (Some(seen), Some(message)) if seen != inbox.generation => {
+                (Some(message.clone()), inbox.generation)
+            }
+            _ => {
+                register_waker(&mut inbox.wakers, cx.waker());
+                if !inbox.installed {
+                    inbox.installed = true;
+                    unsafe { app_message_register_inbox_received(Some(inbox_received)) };
+                }
+                (None, inbox.generation)
+            }
+        });
+        self.generation = Some(generation);
+        match ready {
+            Some(message) => Poll::Ready(message),
+            None => Poll::Pending,
+        }
+    }
+}
+
+struct Buttons {
+    window: *mut Window,
+    previous: (ClickConfigProvider, *mut c_void),
+    waiting: usize,
+    generation: u32,
+    last: Option<ButtonId>,
+    wakers: Vec<Waker>,
+}
+
+static BUTTONS: AppCell<Buttons> = AppCell::new(Buttons {
+    window: null_mut(),
+    previous: (None, null_mut()),
+    waiting: 0,
+    generation: 0,
+    last: None,
+    wakers: Vec::new(),
+});
+
+impl Buttons {
+    /// Take over the click configuration of the top window, if it isn't
+    /// already. Returns `false` if there is no window to take it from.
+    fn install(&mut self) -> bool {
+        if !self.window.is_null() {
+            if unsafe { window_stack_contains_window(self.window) } {
+                return true;
+            }
+            // The window left the stack and may have been destroyed, so there
+            // is nothing to restore.
+            self.window = null_mut();
+            self.previous = (None, null_mut());
+        }
+        let window = unsafe { window_stack_get_top_window() };
+        if window.is_null() {
+            return false;
+        }
+        unsafe {
+            self.previous = (
+                window_get_click_config_provider(window),
+                window_get_click_config_context(window),
+            );
+            window_set_click_config_provider_with_context(
+                window,
+                Some(button_click_config_provider),
+                null_mut(),
+            );
+        }
+        self.window = window;
+        true
+    }
+
+    fn restore(&mut self) {
+        if self.window.is_null() {
+            return;
+        }
+        if unsafe { window_stack_contains_window(self.window) } {
+            let (provider, context) = self.previous;
+            unsafe {
+                window_set_click_config_provider_with_context(self.window, provider, context)
+            };
+        }
+        self.window = null_mut();
+        self.previous = (None, null_mut());
+    }
+}
+
+unsafe extern "C" fn button_click_config_provider(_context: *mut c_void) {
+    // The back button is left alone so the user can still leave the app.
+    for button in [ButtonId::Up, ButtonId::Select, ButtonId::Down] {
+        unsafe { window_single_click_subscribe(button.raw(), Some(button_click_handler)) };
+    }
+}
+
+unsafe extern "C" fn button_click_handler(recognizer: ClickRecognizerRef, _context: *mut c_void) {
+    let button = ButtonId::from_raw(unsafe { click_recognizer_get_button_id(recognizer) });
+    let wakers = BUTTONS.with(|buttons| {
+        buttons.generation = buttons.generation.wrapping_add(1);
+        buttons.last = button;
+        core::mem::take(&mut buttons.wakers)
+    });
+    wakers.into_iter().for_each(Waker::wake);
+}
+
+/// Future returned by [`button_press`].
+pub struct ButtonPress {
+    generation: Option<u32>,
+}
+
+/// Wait for the up, select, or down button to be pressed. Resolves to `None` if
+/// the window stack is empty, since there is no window to take clicks from.
+///
+/// While waiting, this replaces the click configuration of the window on top of
+/// the window stack. The original configuration is restored once no futures
+/// are waiting for a press, unless the window has left the stack by then.
+pub fn button_press() -> ButtonPress {
+    ButtonPress { generation: None }
+}
+
+impl Future for ButtonPress {
+    type Output = Option<ButtonId>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ButtonId>> {
+        let seen = self.generation;
+        let (ready, generation) = BUTTONS.with(|buttons| {
+            if seen.is_none() {
+                buttons.waiting += 1;
+            }
+            match (seen, buttons.last) {
+                (Some(seen), Some(button)) if seen != buttons.generation => {
+                    (Some(Some(button)), buttons.generation)
+                }
+                _ if !buttons.install() => (Some(None), buttons.generation),
+                _ => {
+                    register_waker(&mut buttons.wakers, cx.waker());
+                    (None, buttons.generation)
+                }
+            }
+        });
+        match ready {
+            Some(button) => {
+                // Stop counting this future as waiting.
+                self.generation = None;
+                BUTTONS.with(|buttons| {
+                    buttons.waiting -= 1;
+                    if buttons.waiting == 0 {
+                        buttons.restore();
+                    }
+                });
+                Poll::Ready(button)
+            }
+            None => {
+                self.generation = Some(generation);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for ButtonPress {
+    fn drop(&mut self) {
+        if self.generation.is_some() {
+            BUTTONS.with(|buttons| {
+                buttons.waiting -= 1;
+                if buttons.waiting == 0 {
+                    buttons.restore();
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! The executor behind [`spawn`].
+//!
+//! It only calls into PebbleOS through [`crate::sys`], so the `pebblesdk-build`
+//! crate can test it on the host against a fake `app_timer_register`.
+
+use core::cell::{Cell, RefCell};
+use core::ffi::c_void;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, RawWaker, RawWakerVTable, Waker};
+
+use rust_alloc::boxed::Box;
+use rust_alloc::rc::Rc;
+
+use crate::sys::app_timer_register;
+
+struct Task {
+    future: RefCell<Option<Pin<Box<dyn Future<Output = ()>>>>>,
+    scheduled: Cell<bool>,
+}
+
+impl Task {
+    fn schedule(self: Rc<Task>) {
+        if self.scheduled.replace(true) {
+            return;
+        }
+        let task = Rc::into_raw(self);
+        let timer = unsafe { app_timer_register(0, Some(run_task), task as *mut c_void) };
+        if timer.is_null() {
+            let task = unsafe { Rc::from_raw(task) };
+            task.scheduled.set(false);
+        }
+    }
+}
+
+unsafe extern "C" fn run_task(data: *mut c_void) {
+    let task = unsafe { Rc::from_raw(data as *const Task) };
+    task.scheduled.set(false);
+
+    let waker = waker(task.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut future = task.future.borrow_mut();
+    if let Some(running) = future.as_mut() {
+        if running.as_mut().poll(&mut cx).is_ready() {
+            *future = None;
+        }
+    }
+}
+
+// `Waker` is required to be `Send` and `Sync`, which an `Rc` is not. This is
+// sound only because PebbleOS apps never run on more than one thread.
+static WAKER_VTABLE: RawWakerVTable =
+    RawWakerVTable::new(waker_clone, waker_wake, waker_wake_by_ref, waker_drop);
+
+fn waker(task: Rc<Task>) -> Waker {
+    let raw = RawWaker::new(Rc::into_raw(task) as *const (), &WAKER_VTABLE);
+    unsafe { Waker::from_raw(raw) }
+}
+
+unsafe fn waker_clone(data: *const ()) -> RawWaker {
+    unsafe { Rc::increment_strong_count(data as *const Task) };
+    RawWaker::new(data, &WAKER_VTABLE)
+}
+
+unsafe fn waker_wake(data: *const ()) {
+    unsafe { Rc::from_raw(data as *const Task) }.schedule();
+}
+
+unsafe fn waker_wake_by_ref(data: *const ()) {
+    unsafe {
+        Rc::increment_strong_count(data as *const Task);
+        Rc::from_raw(data as *const Task).schedule();
+    }
+}
+
+unsafe fn waker_drop(data: *const ()) {
+    unsafe { Rc::decrement_strong_count(data as *const Task) };
+}
+
+/// Run a future on the event loop. It is first polled on the next pass through
+/// the loop, so this can be called before `app_event_loop` starts.
+pub fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    let task = Rc::new(Task {
+        future: RefCell::new(Some(Box::pin(future))),
+        scheduled: Cell::new(false),
+    });
+    task.schedule();
+}
+
+#[cfg(test)]
+mod tests {
+    use core::task::Poll;
+
+    use rust_alloc::vec::Vec;
+
+    use super::*;
+    use crate::sys::run_timers;
+
+    /// A future that is woken from outside, like the ones in the parent module.
+    #[derive(Default)]
+    struct Signal {
+        ready: Cell<bool>,
+        polls: Cell<u32>,
+        waker: RefCell<Option<Waker>>,
+    }
+
+    impl Signal {
+        fn wait(self: &Rc<Signal>) -> impl Future<Output = ()> {
+            let signal = self.clone();
+            core::future::poll_fn(move |cx| {
+                signal.polls.set(signal.polls.get() + 1);
+                if signal.ready.get() {
+                    return Poll::Ready(());
+                }
+                signal.waker.replace(Some(cx.waker().clone()));
+                Poll::Pending
+            })
+        }
+
+        fn fire(&self) {
+            self.ready.set(true);
+            if let Some(waker) = self.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    #[test]
+    fn polled_on_next_pass() {
+        let done = Rc::new(Cell::new(false));
+        let task_done = done.clone();
+        spawn(async move { task_done.set(true) });
+        assert!(!done.get());
+        assert_eq!(run_timers(), 1);
+        assert!(done.get());
+        assert_eq!(run_timers(), 0);
+    }
+
+    #[test]
+    fn woken_task_resumes() {
+        let signal = Rc::new(Signal::default());
+        let order = Rc::new(RefCell::new(Vec::new()));
+        let (wait, task_order) = (signal.wait(), order.clone());
+        spawn(async move {
+            task_order.borrow_mut().push(1);
+            wait.await;
+            task_order.borrow_mut().push(2);
+        });
+        run_timers();
+        assert_eq!(*order.borrow(), [1]);
+        assert_eq!(run_timers(), 0);
+
+        signal.fire();
+        assert_eq!(run_timers(), 1);
+        assert_eq!(*order.borrow(), [1, 2]);
+        assert_eq!(signal.polls.get(), 2);
+    }
+
+    #[test]
+    fn repeated_wakes_poll_once() {
+        let signal = Rc::new(Signal::default());
+        spawn(signal.wait());
+        run_timers();
+        let waker = signal.waker.borrow().clone().unwrap();
+        waker.wake_by_ref();
+        waker.wake_by_ref();
+        waker.wake();
+        assert_eq!(run_timers(), 1);
+        assert_eq!(signal.polls.get(), 2);
+    }
+
+    #[test]
+    fn finished_task_ignores_wakes() {
+        let signal = Rc::new(Signal::default());
+        spawn(signal.wait());
+        run_timers();
+        let stale = signal.waker.borrow().clone().unwrap();
+        signal.fire();
+        run_timers();
+        assert_eq!(signal.polls.get(), 2);
+
+        stale.wake();
+        assert_eq!(run_timers(), 1);
+        assert_eq!(signal.polls.get(), 2);
+    }
+
+    #[test]
+    fn forgotten_task_is_freed() {
+        struct Guard(Rc<Cell<bool>>);
+
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                self.0.set(true);
+            }
+        }
+
+        let signal = Rc::new(Signal::default());
+        let dropped = Rc::new(Cell::new(false));
+        let (wait, guard) = (signal.wait(), Guard(dropped.clone()));
+        spawn(async move {
+            let _guard = guard;
+            wait.await;
+        });
+        run_timers();
+        assert!(!dropped.get());
+
+        // Nothing can wake the task once its waker is gone.
+        signal.waker.take();
+        assert!(dropped.get());
+    }
+}
@@ -0,0 +1,179 @@
+//! Owned copies of AppMessage dictionaries, returned by
+//! [`next_message`](super::next_message).
+//!
+//! Like the executor, this can be tested on the host through the
+//! `pebblesdk-build` crate.
+
+use core::ffi::CStr;
+
+use rust_alloc::vec::Vec;
+
+use crate::sys::{TUPLE_BYTE_ARRAY, TUPLE_CSTRING, TUPLE_INT, TUPLE_UINT};
+
+/// A value stored in an AppMessage dictionary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Value<'a> {
+    Bytes(&'a [u8]),
+    CString(&'a CStr),
+    UInt(u32),
+    Int(i32),
+}
+
+/// An owned copy of a dictionary received over AppMessage.
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub(super) buffer: Vec<u8>,
+}
+
+impl Message {
+    /// Iterate over the key-value pairs in the message.
+    pub fn iter(&self) -> MessageIter<'_> {
+        MessageIter {
+            remaining: self.buffer.first().copied().unwrap_or(0),
+            data: self.buffer.get(1..).unwrap_or(&[]),
+        }
+    }
+
+    /// Find the value stored under a key.
+    pub fn get(&self, key: u32) -> Option<Value<'_>> {
+        self.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+}
+
+/// Iterator over the contents of a [`Message`].
+#[derive(Clone, Debug)]
+pub struct MessageIter<'a> {
+    remaining: u8,
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for MessageIter<'a> {
+    type Item = (u32, Value<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Each tuple is a packed little-endian header of a 32-bit key, an 8-bit
+        // type, and a 16-bit length, followed by the value.
+        if self.remaining == 0 || self.data.len() < 7 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let (header, rest) = self.data.split_at(7);
+        let key = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let kind = header[4] as u32;
+        let length = u16::from_le_bytes([header[5], header[6]]) as usize;
+        if rest.len() < length {
+            self.data = &[];
+            return None;
+        }
+        let (value, rest) = rest.split_at(length);
+        self.data = rest;
+
+        let mut int = [0; 4];
+        let value = match kind {
+            TUPLE_BYTE_ARRAY => Value::Bytes(value),
+            TUPLE_CSTRING => Value::CString(CStr::from_bytes_until_nul(value).ok()?),
+            TUPLE_UINT if length <= 4 => {
+                int[..length].copy_from_slice(value);
+                Value::UInt(u32::from_le_bytes(int))
+            }
+            TUPLE_INT if length <= 4 => {
+                int[..length].copy_from_slice(value);
+                // Sign extend from the size that was actually sent.
+                let shift = 32 - 8 * length.max(1) as u32;
+                Value::Int(i32::from_le_bytes(int) << shift >> shift)
+            }
+            _ => return None,
+        };
+        Some((key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_alloc::vec;
+
+    use super::*;
+
+    fn tuple(buffer: &mut Vec<u8>, key: u32, kind: u32, value: &[u8]) {
+        buffer.extend_from_slice(&key.to_le_bytes());
+        buffer.push(kind as u8);
+        buffer.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        buffer.extend_from_slice(value);
+    }
+
+    fn message(tuples: &[(u32, u32, &[u8])]) -> Message {
+        let mut buffer = vec![tuples.len() as u8];
+        for &(key, kind, value) in tuples {
+            tuple(&mut buffer, key, kind, value);
+        }
+        Message { buffer }
+    }
+
+    #[test]
+    fn values() {
+        let message = message(&[
+            (1, TUPLE_BYTE_ARRAY, &[1, 2, 3]),
+            (2, TUPLE_CSTRING, b"hi\0"),
+            (3, TUPLE_UINT, &0xdeadbeef_u32.to_le_bytes()),
+            (4, TUPLE_INT, &(-2_i32).to_le_bytes()),
+        ]);
+        assert_eq!(
+            message.iter().collect::<Vec<_>>(),
+            [
+                (1, Value::Bytes(&[1, 2, 3])),
+                (2, Value::CString(c"hi")),
+                (3, Value::UInt(0xdeadbeef)),
+                (4, Value::Int(-2)),
+            ]
+        );
+        assert_eq!(message.get(3), Some(Value::UInt(0xdeadbeef)));
+        assert_eq!(message.get(5), None);
+    }
+
+    #[test]
+    fn short_integers() {
+        let message = message(&[
+            (1, TUPLE_UINT, &[0xff]),
+            (2, TUPLE_INT, &[0xff]),
+            (3, TUPLE_INT, &(-300_i16).to_le_bytes()),
+            (4, TUPLE_INT, &[0x7f]),
+        ]);
+        assert_eq!(message.get(1), Some(Value::UInt(255)));
+        assert_eq!(message.get(2), Some(Value::Int(-1)));
+        assert_eq!(message.get(3), Some(Value::Int(-300)));
+        assert_eq!(message.get(4), Some(Value::Int(127)));
+    }
+
+    #[test]
+    fn count_limits_tuples() {
+        let mut message = message(&[(1, TUPLE_UINT, &[1]), (2, TUPLE_UINT, &[2])]);
+        message.buffer[0] = 1;
+        assert_eq!(message.iter().count(), 1);
+    }
+
+    #[test]
+    fn truncated_tuple_ends_iteration() {
+        let mut message = message(&[(1, TUPLE_UINT, &[1]), (2, TUPLE_BYTE_ARRAY, &[1, 2, 3])]);
+        message.buffer.pop();
+        assert_eq!(message.iter().collect::<Vec<_>>(), [(1, Value::UInt(1))]);
+        message.buffer.truncate(5);
+        assert_eq!(message.iter().count(), 0);
+    }
+
+    #[test]
+    fn malformed_values_end_iteration() {
+        let unterminated = message(&[(1, TUPLE_CSTRING, b"hi"), (2, TUPLE_UINT, &[1])]);
+        assert_eq!(unterminated.iter().count(), 0);
+        let long = message(&[(1, TUPLE_INT, &[0; 8])]);
+        assert_eq!(long.iter().count(), 0);
+        let unknown = message(&[(1, 9, &[])]);
+        assert_eq!(unknown.iter().count(), 0);
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(Message { buffer: Vec::new() }.iter().count(), 0);
+        assert_eq!(message(&[]).iter().count(), 0);
+    }
+}
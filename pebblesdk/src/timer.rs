@@ -0,0 +1,229 @@
+//! Callbacks scheduled to run on the app's event loop after a delay.
+
+use core::ffi::c_void;
+use core::ptr::null_mut;
+
+use rust_alloc::boxed::Box;
+
+use pebblesdk_sys::{app_timer_cancel, app_timer_register, app_timer_reschedule, AppTimer};
+
+struct TimerState {
+    handle: *mut AppTimer,
+    callback: Option<Box<dyn FnOnce()>>,
+}
+
+/// A closure that runs once after a delay. Dropping the timer before it fires
+/// cancels it.
+#[must_use = "the timer is cancelled when dropped"]
+pub struct Timer {
+    state: *mut TimerState,
+}
+
+unsafe extern "C" fn timer_trampoline(data: *mut c_void) {
+    let state = data as *mut TimerState;
+    // PebbleOS frees the timer after it fires. Take everything needed out of
+    // the state first, since the callback is allowed to drop its own `Timer`.
+    let callback = unsafe {
+        (*state).handle = null_mut();
+        (*state).callback.take()
+    };
+    if let Some(callback) = callback {
+        callback();
+    }
+}
+
+impl Timer {
+    /// Run `callback` after `timeout_ms` milliseconds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS fails to create the timer.
+    pub fn after<F>(timeout_ms: u32, callback: F) -> Timer
+    where
+        F: FnOnce() + 'static,
+    {
+        let state = Box::into_raw(Box::new(TimerState {
+            handle: null_mut(),
+            callback: Some(Box::new(callback)),
+        }));
+        unsafe {
+            let handle =
+                app_timer_register(timeout_ms, Some(timer_trampoline), state as *mut c_void);
+            if handle.is_null() {
+                drop(Box::from_raw(state));
+                panic!("Failed to register timer");
+            }
+            (*state).handle = handle;
+        }
+        Timer { state }
+    }
+
+    /// True if the timer has not yet fired.
+    pub fn is_pending(&self) -> bool {
+        unsafe { !(*self.state).handle.is_null() }
+    }
+
+    /// Restart the countdown with a new timeout. Returns false if the timer
+    /// has already fired.
+    pub fn reschedule(&mut self, timeout_ms: u32) -> bool {
+        let handle = unsafe { (*self.state).handle };
+        !handle.is_null() && unsafe { app_timer_reschedule(handle, timeout_ms) }
+    }
+
+    /// Stop the timer without running it. Equivalent to dropping it.
+    pub fn cancel(self) {}
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        unsafe {
+            let handle = (*self.state).handle;
+            if !handle.is_null() {
+                app_timer_cancel(handle);
+            }
+            drop(Box::from_raw(self.state));
+        }
+    }
+}
+
+/// Run `f` once control returns to the event loop, such as to destroy
+/// something from inside one of its own callbacks while PebbleOS is still
+/// using it.
+///
+/// # Panics
+///
+/// Panics if PebbleOS fails to create the timer, rather than silently never
+/// running `f`.
+pub(crate) fn defer<F: FnOnce() + 'static>(f: F) {
+    let f = Box::into_raw(Box::new(Box::new(f) as Box<dyn FnOnce()>));
+    let handle = unsafe { app_timer_register(0, Some(deferred), f as *mut c_void) };
+    if handle.is_null() {
+        // Running `f` now could free something PebbleOS is still using.
+        panic!("Failed to register timer");
+    }
+}
+
+unsafe extern "C" fn deferred(data: *mut c_void) {
+    let f = unsafe { Box::from_raw(data as *mut Box<dyn FnOnce()>) };
+    f();
+}
+
+struct IntervalState {
+    handle: *mut AppTimer,
+    interval_ms: u32,
+    // Taken while the callback is running.
+    callback: Option<Box<dyn FnMut()>>,
+    // Set if the `Interval` is dropped by its own callback.
+    dropped: bool,
+}
+
+/// A closure that runs repeatedly with a fixed delay between each call.
+/// Dropping the interval stops it.
+///
+/// Each call is scheduled after the previous one returns. If PebbleOS fails
+/// to schedule it, such as when the app is out of memory, the interval stops,
+/// which [`Interval::is_running`] reports.
+#[must_use = "the timer is cancelled when dropped"]
+pub struct Interval {
+    state: *mut IntervalState,
+}
+
+unsafe extern "C" fn interval_trampoline(data: *mut c_void) {
+    let state = data as *mut IntervalState;
+    let mut callback = unsafe {
+        (*state).handle = null_mut();
+        match (*state).callback.take() {
+            Some(callback) => callback,
+            None => return,
+        }
+    };
+
+    callback();
+
+    unsafe {
+        if (*state).dropped {
+            drop(callback);
+            drop(Box::from_raw(state));
+            return;
+        }
+        (*state).callback = Some(callback);
+        // The next call is scheduled after this one finishes, so a slow
+        // callback can't queue up a backlog of timers. If this fails, the
+        // handle stays null and `is_running` reports the interval stopped.
+        (*state).handle = app_timer_register(
+            (*state).interval_ms,
+            Some(interval_trampoline),
+            state as *mut c_void,
+        );
+    }
+}
+
+impl Interval {
+    /// Run `callback` every `interval_ms` milliseconds, starting
+    /// `interval_ms` milliseconds from now.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS fails to create the timer.
+    pub fn every<F>(interval_ms: u32, callback: F) -> Interval
+    where
+        F: FnMut() + 'static,
+    {
+        let state = Box::into_raw(Box::new(IntervalState {
+            handle: null_mut(),
+            interval_ms,
+            callback: Some(Box::new(callback)),
+            dropped: false,
+        }));
+        unsafe {
+            let handle =
+                app_timer_register(interval_ms, Some(interval_trampoline), state as *mut c_void);
+            if handle.is_null() {
+                drop(Box::from_raw(state));
+                panic!("Failed to register timer");
+            }
+            (*state).handle = handle;
+        }
+        Interval { state }
+    }
+
+    /// False if the interval stopped because PebbleOS failed to schedule its
+    /// next call.
+    pub fn is_running(&self) -> bool {
+        // While the callback is running it has been taken out of the state,
+        // and the next call hasn't been scheduled yet.
+        unsafe { !(*self.state).handle.is_null() || (*self.state).callback.is_none() }
+    }
+
+    /// Change the delay between calls, restarting the current countdown.
+    pub fn set_interval(&mut self, interval_ms: u32) {
+        unsafe {
+            (*self.state).interval_ms = interval_ms;
+            let handle = (*self.state).handle;
+            if !handle.is_null() {
+                app_timer_reschedule(handle, interval_ms);
+            }
+        }
+    }
+
+    /// Stop the interval. Equivalent to dropping it.
+    pub fn cancel(self) {}
+}
+
+impl Drop for Interval {
+    fn drop(&mut self) {
+        unsafe {
+            if (*self.state).callback.is_none() {
+                // The interval is being dropped from inside its own callback,
+                // which is still borrowed. Leave the cleanup to the trampoline.
+                (*self.state).dropped = true;
+                return;
+            }
+            let handle = (*self.state).handle;
+            if !handle.is_null() {
+                app_timer_cancel(handle);
+            }
+            drop(Box::from_raw(self.state));
+        }
+    }
+}
@@ -0,0 +1,325 @@
+//! Angles, and fixed-point trigonometry in the units PebbleOS uses.
+//!
+//! On the watch, `sin_lookup`, `cos_lookup` and `atan2_lookup` call the
+//! firmware's own functions. There's no pure-Rust copy of them, since the
+//! firmware's tables aren't available here. Instead [`sin_approx`],
+//! [`cos_approx`] and [`atan2_approx`] run anywhere, using tables computed
+//! from the exact functions. Their results are within a unit or two of the
+//! exact value, and may differ from the firmware's by as much.
+//!
+//! [`Angle`] uses the firmware on the watch and the approximations elsewhere,
+//! so host tests of code built on it shouldn't pin exact values. The
+//! `pebblesdk-build` crate builds this module for use on the host, and runs
+//! its tests.
+
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+/// The number of units in a full turn.
+pub const TRIG_MAX_ANGLE: i32 = 0x10000;
+
+/// The value of [`Angle::sin`] and [`Angle::cos`] representing 1.
+pub const TRIG_MAX_RATIO: i32 = 0xffff;
+
+const TRIG_PI: i32 = TRIG_MAX_ANGLE / 2;
+
+/// An angle, in units of 1/[`TRIG_MAX_ANGLE`] of a turn.
+///
+/// On screen, where y grows downwards, positive angles turn clockwise. Clock
+/// hands at an angle from 12 o'clock point towards `(sin, -cos)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Angle(i32);
+
+impl Angle {
+    pub const ZERO: Angle = Angle(0);
+    pub const FULL_TURN: Angle = Angle(TRIG_MAX_ANGLE);
+
+    /// Create an angle from PebbleOS units.
+    pub const fn from_raw(raw: i32) -> Angle {
+        Angle(raw)
+    }
+
+    /// Create an angle from whole degrees, like `DEG_TO_TRIGANGLE`.
+    pub const fn from_degrees(degrees: i32) -> Angle {
+        Angle((degrees as i64 * TRIG_MAX_ANGLE as i64 / 360) as i32)
+    }
+
+    pub fn from_radians(radians: f32) -> Angle {
+        Angle((radians * TRIG_PI as f32 / core::f32::consts::PI) as i32)
+    }
+
+    /// The angle from the positive x axis to the point `(x, y)`, within
+    /// `0..TRIG_MAX_ANGLE`.
+    pub fn atan2(y: i16, x: i16) -> Angle {
+        Angle(atan2(y, x))
+    }
+
+    /// The angle in PebbleOS units.
+    pub const fn raw(self) -> i32 {
+        self.0
+    }
+
+    /// The angle in whole degrees, rounded towards zero, like
+    /// `TRIGANGLE_TO_DEG`.
+    pub const fn degrees(self) -> i32 {
+        (self.0 as i64 * 360 / TRIG_MAX_ANGLE as i64) as i32
+    }
+
+    pub fn radians(self) -> f32 {
+        self.0 as f32 * core::f32::consts::PI / TRIG_PI as f32
+    }
+
+    /// The same angle, within `0..TRIG_MAX_ANGLE`.
+    pub const fn normalized(self) -> Angle {
+        Angle(self.0.rem_euclid(TRIG_MAX_ANGLE))
+    }
+
+    /// The sine of the angle, scaled by [`TRIG_MAX_RATIO`].
+    pub fn sin(self) -> i32 {
+        sin(self.0)
+    }
+
+    /// The cosine of the angle, scaled by [`TRIG_MAX_RATIO`].
+    pub fn cos(self) -> i32 {
+        cos(self.0)
+    }
+}
+
+impl Add for Angle {
+    type Output = Angle;
+
+    fn add(self, other: Angle) -> Angle {
+        Angle(self.0.wrapping_add(other.0))
+    }
+}
+
+impl Sub for Angle {
+    type Output = Angle;
+
+    fn sub(self, other: Angle) -> Angle {
+        Angle(self.0.wrapping_sub(other.0))
+    }
+}
+
+impl Neg for Angle {
+    type Output = Angle;
+
+    fn neg(self) -> Angle {
+        Angle(self.0.wrapping_neg())
+    }
+}
+
+impl Mul<i32> for Angle {
+    type Output = Angle;
+
+    fn mul(self, factor: i32) -> Angle {
+        Angle(self.0.wrapping_mul(factor))
+    }
+}
+
+impl Div<i32> for Angle {
+    type Output = Angle;
+
+    fn div(self, divisor: i32) -> Angle {
+        Angle(self.0 / divisor)
+    }
+}
+
+// `Angle` matches other watch code exactly where it can.
+#[cfg(not(target_os = "none"))]
+use self::{atan2_approx as atan2, cos_approx as cos, sin_approx as sin};
+#[cfg(target_os = "none")]
+use self::{atan2_lookup as atan2, cos_lookup as cos, sin_lookup as sin};
+
+/// The sine of `angle`, scaled by [`TRIG_MAX_RATIO`], from PebbleOS.
+#[cfg(target_os = "none")]
+pub fn sin_lookup(angle: i32) -> i32 {
+    unsafe { pebblesdk_sys::sin_lookup(angle) }
+}
+
+/// The cosine of `angle`, scaled by [`TRIG_MAX_RATIO`], from PebbleOS.
+#[cfg(target_os = "none")]
+pub fn cos_lookup(angle: i32) -> i32 {
+    unsafe { pebblesdk_sys::cos_lookup(angle) }
+}
+
+/// The angle from the positive x axis to the point `(x, y)`, within
+/// `0..TRIG_MAX_ANGLE`, from PebbleOS.
+#[cfg(target_os = "none")]
+pub fn atan2_lookup(y: i16, x: i16) -> i32 {
+    unsafe { pebblesdk_sys::atan2_lookup(y, x) }
+}
+
+// `TRIG_MAX_RATIO * sin(a)` for the first quarter turn, every 64 units.
+const SIN_TABLE: [u16; 257] = [
+    0, 402, 804, 1206, 1608, 2010, 2412, 2814, 3216, 3617, 4019, 4420, 4821, 5222, 5623, 6023,
+    6424, 6824, 7223, 7623, 8022, 8421, 8820, 9218, 9616, 10014, 10411, 10808, 11204, 11600, 11996,
+    12391, 12785, 13179, 13573, 13966, 14359, 14751, 15142, 15533, 15924, 16313, 16703, 17091,
+    17479, 17866, 18253, 18639, 19024, 19408, 19792, 20175, 20557, 20939, 21319, 21699, 22078,
+    22456, 22834, 23210, 23586, 23960, 24334, 24707, 25079, 25450, 25820, 26189, 26557, 26925,
+    27291, 27656, 28020, 28383, 28745, 29106, 29465, 29824, 30181, 30538, 30893, 31247, 31600,
+    31952, 32302, 32651, 32999, 33346, 33692, 34036, 34379, 34721, 35061, 35400, 35738, 36074,
+    36409, 36743, 37075, 37406, 37736, 38064, 38390, 38715, 39039, 39361, 39682, 40001, 40319,
+    40635, 40950, 41263, 41575, 41885, 42194, 42500, 42806, 43109, 43411, 43712, 44011, 44308,
+    44603, 44897, 45189, 45479, 45768, 46055, 46340, 46624, 46905, 47185, 47464, 47740, 48014,
+    48287, 48558, 48827, 49095, 49360, 49624, 49885, 50145, 50403, 50659, 50913, 51166, 51416,
+    51664, 51911, 52155, 52398, 52638, 52877, 53113, 53348, 53580, 53811, 54039, 54266, 54490,
+    54713, 54933, 55151, 55367, 55582, 55794, 56003, 56211, 56417, 56620, 56822, 57021, 57218,
+    57413, 57606, 57797, 57985, 58171, 58356, 58537, 58717, 58895, 59070, 59243, 59414, 59582,
+    59749, 59913, 60075, 60234, 60391, 60546, 60699, 60850, 60998, 61144, 61287, 61429, 61567,
+    61704, 61838, 61970, 62100, 62227, 62352, 62475, 62595, 62713, 62829, 62942, 63053, 63161,
+    63267, 63371, 63472, 63571, 63668, 63762, 63853, 63943, 64030, 64114, 64196, 64276, 64353,
+    64428, 64500, 64570, 64638, 64703, 64765, 64826, 64883, 64939, 64992, 65042, 65090, 65136,
+    65179, 65219, 65258, 65293, 65327, 65357, 65386, 65412, 65435, 65456, 65475, 65491, 65504,
+    65515, 65524, 65530, 65534, 65535,
+];
+
+// `atan(i / 256)` in angle units, for i from 0 to 256.
+const ATAN_TABLE: [u16; 257] = [
+    0, 41, 81, 122, 163, 204, 244, 285, 326, 367, 407, 448, 489, 529, 570, 610, 651, 692, 732, 773,
+    813, 854, 894, 935, 975, 1015, 1056, 1096, 1136, 1177, 1217, 1257, 1297, 1337, 1377, 1417,
+    1457, 1497, 1537, 1577, 1617, 1656, 1696, 1736, 1775, 1815, 1854, 1894, 1933, 1973, 2012, 2051,
+    2090, 2129, 2168, 2207, 2246, 2285, 2324, 2363, 2401, 2440, 2478, 2517, 2555, 2594, 2632, 2670,
+    2708, 2746, 2784, 2822, 2860, 2897, 2935, 2973, 3010, 3047, 3085, 3122, 3159, 3196, 3233, 3270,
+    3307, 3344, 3380, 3417, 3453, 3490, 3526, 3562, 3599, 3635, 3670, 3706, 3742, 3778, 3813, 3849,
+    3884, 3920, 3955, 3990, 4025, 4060, 4095, 4129, 4164, 4199, 4233, 4267, 4302, 4336, 4370, 4404,
+    4438, 4471, 4505, 4539, 4572, 4605, 4639, 4672, 4705, 4738, 4771, 4803, 4836, 4869, 4901, 4933,
+    4966, 4998, 5030, 5062, 5094, 5125, 5157, 5188, 5220, 5251, 5282, 5313, 5344, 5375, 5406, 5437,
+    5467, 5498, 5528, 5559, 5589, 5619, 5649, 5679, 5708, 5738, 5768, 5797, 5826, 5856, 5885, 5914,
+    5943, 5972, 6000, 6029, 6058, 6086, 6114, 6142, 6171, 6199, 6227, 6254, 6282, 6310, 6337, 6365,
+    6392, 6419, 6446, 6473, 6500, 6527, 6554, 6580, 6607, 6633, 6660, 6686, 6712, 6738, 6764, 6790,
+    6815, 6841, 6867, 6892, 6917, 6943, 6968, 6993, 7018, 7043, 7068, 7092, 7117, 7141, 7166, 7190,
+    7214, 7238, 7262, 7286, 7310, 7334, 7358, 7381, 7405, 7428, 7451, 7475, 7498, 7521, 7544, 7566,
+    7589, 7612, 7635, 7657, 7679, 7702, 7724, 7746, 7768, 7790, 7812, 7834, 7856, 7877, 7899, 7920,
+    7942, 7963, 7984, 8005, 8026, 8047, 8068, 8089, 8110, 8131, 8151, 8172, 8192,
+];
+
+/// Look up a value in a table sampled every `1 << shift` units, interpolating
+/// linearly between samples.
+fn interpolate(table: &[u16], value: u32, shift: u32) -> i32 {
+    let index = (value >> shift) as usize;
+    let fraction = (value & ((1 << shift) - 1)) as i32;
+    let low = table[index] as i32;
+    let Some(&high) = table.get(index + 1) else {
+        return low;
+    };
+    low + ((high as i32 - low) * fraction + (1 << (shift - 1))) / (1 << shift)
+}
+
+/// The sine of `angle`, scaled by [`TRIG_MAX_RATIO`], to within a unit or two.
+/// Not bit-compatible with PebbleOS's `sin_lookup`.
+pub fn sin_approx(angle: i32) -> i32 {
+    // Reduce to the first quarter turn, remembering the sign.
+    let mut angle = angle.rem_euclid(TRIG_MAX_ANGLE);
+    let mut sign = 1;
+    if angle >= TRIG_PI {
+        angle -= TRIG_PI;
+        sign = -1;
+    }
+    if angle > TRIG_PI / 2 {
+        angle = TRIG_PI - angle;
+    }
+    sign * interpolate(&SIN_TABLE, angle as u32, 6)
+}
+
+/// The cosine of `angle`, scaled by [`TRIG_MAX_RATIO`], to within a unit or
+/// two. Not bit-compatible with PebbleOS's `cos_lookup`.
+pub fn cos_approx(angle: i32) -> i32 {
+    sin_approx(angle.wrapping_add(TRIG_PI / 2))
+}
+
+/// The angle from the positive x axis to the point `(x, y)`, within
+/// `0..TRIG_MAX_ANGLE`, to within a unit or two. Returns 0 for the origin.
+/// Not bit-compatible with PebbleOS's `atan2_lookup`.
+pub fn atan2_approx(y: i16, x: i16) -> i32 {
+    if x == 0 && y == 0 {
+        return 0;
+    }
+    let (abs_x, abs_y) = (x.unsigned_abs() as u32, y.unsigned_abs() as u32);
+    // The angle within the first eighth of a turn, from the ratio of the
+    // shorter side to the longer, in 1/65536ths.
+    let (short, long) = (abs_x.min(abs_y), abs_x.max(abs_y));
+    let ratio = (short << 16) / long;
+    let eighth = interpolate(&ATAN_TABLE, ratio, 8);
+    let quarter = if abs_y > abs_x {
+        TRIG_MAX_ANGLE / 4 - eighth
+    } else {
+        eighth
+    };
+    let angle = match (x < 0, y < 0) {
+        (false, false) => quarter,
+        (true, false) => TRIG_PI - quarter,
+        (true, true) => TRIG_PI + quarter,
+        (false, true) => TRIG_MAX_ANGLE - quarter,
+    };
+    angle % TRIG_MAX_ANGLE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sin_quadrant_boundaries() {
+        assert_eq!(sin_approx(0), 0);
+        assert_eq!(sin_approx(TRIG_MAX_ANGLE / 4), TRIG_MAX_RATIO);
+        assert_eq!(sin_approx(TRIG_MAX_ANGLE / 2), 0);
+        assert_eq!(sin_approx(TRIG_MAX_ANGLE * 3 / 4), -TRIG_MAX_RATIO);
+        assert_eq!(sin_approx(TRIG_MAX_ANGLE), 0);
+        assert_eq!(cos_approx(0), TRIG_MAX_RATIO);
+        assert_eq!(cos_approx(TRIG_MAX_ANGLE / 4), 0);
+        assert_eq!(cos_approx(TRIG_MAX_ANGLE / 2), -TRIG_MAX_RATIO);
+    }
+
+    #[test]
+    fn sin_of_thirty_degrees() {
+        // `TRIG_MAX_ANGLE / 12` rounds down to just under 30 degrees, whose
+        // sine is 32765.7 units.
+        assert_eq!(sin_approx(TRIG_MAX_ANGLE / 12), 32765);
+        assert_eq!(cos_approx(TRIG_MAX_ANGLE / 6 + 1), 32765);
+    }
+
+    #[test]
+    fn sin_of_negative_angles() {
+        assert_eq!(sin_approx(-TRIG_MAX_ANGLE / 4), -TRIG_MAX_RATIO);
+        assert_eq!(
+            sin_approx(-TRIG_MAX_ANGLE / 12),
+            -sin_approx(TRIG_MAX_ANGLE / 12)
+        );
+        for angle in (0..TRIG_MAX_ANGLE).step_by(97) {
+            assert_eq!(sin_approx(-angle), -sin_approx(angle), "angle {angle}");
+            assert_eq!(
+                sin_approx(angle - TRIG_MAX_ANGLE),
+                sin_approx(angle),
+                "angle {angle}"
+            );
+        }
+    }
+
+    #[test]
+    fn atan2_on_axes() {
+        assert_eq!(atan2_approx(0, 0), 0);
+        assert_eq!(atan2_approx(0, 1), 0);
+        assert_eq!(atan2_approx(1, 0), TRIG_MAX_ANGLE / 4);
+        assert_eq!(atan2_approx(0, -1), TRIG_MAX_ANGLE / 2);
+        assert_eq!(atan2_approx(-1, 0), TRIG_MAX_ANGLE * 3 / 4);
+        assert_eq!(atan2_approx(0, i16::MIN), TRIG_MAX_ANGLE / 2);
+        assert_eq!(atan2_approx(i16::MIN, 0), TRIG_MAX_ANGLE * 3 / 4);
+    }
+
+    #[test]
+    fn atan2_on_diagonals() {
+        assert_eq!(atan2_approx(i16::MAX, i16::MAX), TRIG_MAX_ANGLE / 8);
+        assert_eq!(atan2_approx(i16::MIN, i16::MIN), TRIG_MAX_ANGLE * 5 / 8);
+        assert_eq!(atan2_approx(1, -1), TRIG_MAX_ANGLE * 3 / 8);
+        assert_eq!(atan2_approx(-1, 1), TRIG_MAX_ANGLE * 7 / 8);
+    }
+
+    #[test]
+    fn atan2_inverts_sin_and_cos() {
+        for angle in (0..TRIG_MAX_ANGLE).step_by(61) {
+            let (y, x) = (sin_approx(angle) / 2, cos_approx(angle) / 2);
+            let error = (atan2_approx(y as i16, x as i16) - angle).rem_euclid(TRIG_MAX_ANGLE);
+            assert!(error <= 2 || error >= TRIG_MAX_ANGLE - 2, "angle {angle}");
+        }
+    }
+}
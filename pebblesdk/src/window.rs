@@ -0,0 +1,73 @@
+//! Full-screen windows, the top level of an app's user interface.
+
+use pebblesdk_sys::{
+    window_create, window_destroy, window_get_root_layer,
+    window_set_click_config_provider_with_context, Window as RawWindow,
+};
+
+use crate::click::{click_config_provider, ClickConfig, SharedClickConfig};
+use crate::layer::Layer;
+
+pub mod action_menu;
+pub mod number_window;
+pub mod stack;
+
+/// An owned PebbleOS window, destroyed when dropped.
+pub struct Window {
+    raw: *mut RawWindow,
+    click_config: Option<SharedClickConfig>,
+}
+
+impl Window {
+    /// Create a new window.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory.
+    pub fn new() -> Window {
+        let raw = unsafe { window_create() };
+        assert!(!raw.is_null(), "Failed to create window");
+        Window {
+            raw,
+            click_config: None,
+        }
+    }
+
+    /// Get the underlying PebbleOS window, for use with [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut RawWindow {
+        self.raw
+    }
+
+    /// The layer filling the window, which all of its other layers are added
+    /// to.
+    pub fn root_layer(&self) -> &Layer {
+        unsafe { Layer::from_ptr(window_get_root_layer(self.raw)) }
+    }
+
+    /// Set the handlers called when buttons are pressed while this window is
+    /// on top of the window stack, replacing any previous configuration.
+    pub fn set_click_config(&mut self, config: ClickConfig) {
+        let config = SharedClickConfig::new(config);
+        unsafe {
+            window_set_click_config_provider_with_context(
+                self.raw,
+                Some(click_config_provider),
+                config.context(),
+            )
+        };
+        // Only release the old config after PebbleOS has let go of it.
+        self.click_config = Some(config);
+    }
+}
+
+impl Default for Window {
+    fn default() -> Window {
+        Window::new()
+    }
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        unsafe { window_destroy(self.raw) }
+    }
+}
@@ -0,0 +1,316 @@
+//! Menus of actions that slide in from the side, with nested levels.
+
+use core::cell::RefCell;
+use core::ffi::c_void;
+use core::ptr::null_mut;
+
+use rust_alloc::boxed::Box;
+use rust_alloc::ffi::CString;
+use rust_alloc::vec::Vec;
+
+use pebblesdk_sys::{
+    action_menu_close, action_menu_freeze, action_menu_hierarchy_destroy,
+    action_menu_item_get_action_data, action_menu_level_add_action, action_menu_level_add_child,
+    action_menu_level_create, action_menu_level_set_display_mode, action_menu_open,
+    action_menu_set_result_window, action_menu_unfreeze, ActionMenu as RawActionMenu,
+    ActionMenuAlign as RawActionMenuAlign, ActionMenuAlignCenter, ActionMenuAlignTop,
+    ActionMenuConfig, ActionMenuConfig__bindgen_ty_1 as ActionMenuColors, ActionMenuItem,
+    ActionMenuLevel as RawActionMenuLevel, ActionMenuLevelDisplayMode,
+    ActionMenuLevelDisplayModeThin, ActionMenuLevelDisplayModeWide,
+};
+
+use crate::graphics::Color;
+use crate::window::Window;
+
+/// How the items of an [`ActionMenuLevel`] are laid out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ActionMenuDisplayMode {
+    /// One item per row, with room for long labels.
+    #[default]
+    Wide,
+    /// Several short items (such as emoji) per row.
+    Thin,
+}
+
+impl ActionMenuDisplayMode {
+    fn raw(self) -> ActionMenuLevelDisplayMode {
+        match self {
+            ActionMenuDisplayMode::Wide => ActionMenuLevelDisplayModeWide,
+            ActionMenuDisplayMode::Thin => ActionMenuLevelDisplayModeThin,
+        }
+    }
+}
+
+/// Where the selected item of an [`ActionMenu`] starts out on screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ActionMenuAlign {
+    #[default]
+    Top,
+    Center,
+}
+
+impl ActionMenuAlign {
+    fn raw(self) -> RawActionMenuAlign {
+        match self {
+            ActionMenuAlign::Top => ActionMenuAlignTop,
+            ActionMenuAlign::Center => ActionMenuAlignCenter,
+        }
+    }
+}
+
+/// Access to an open [`ActionMenu`] from within an action.
+pub struct ActionMenuHandle {
+    raw: *mut RawActionMenu,
+    close: Option<bool>,
+}
+
+impl ActionMenuHandle {
+    /// Keep the menu open after the action returns, such as while waiting for
+    /// the result of the action.
+    pub fn freeze(&mut self) {
+        unsafe { action_menu_freeze(self.raw) }
+    }
+
+    pub fn unfreeze(&mut self) {
+        unsafe { action_menu_unfreeze(self.raw) }
+    }
+
+    /// Show `window` in place of the menu once it closes. The window must
+    /// outlive the menu.
+    pub fn set_result_window(&mut self, window: &Window) {
+        unsafe { action_menu_set_result_window(self.raw, window.as_ptr()) }
+    }
+
+    /// Close the menu once the current action returns.
+    pub fn close(&mut self, animated: bool) {
+        self.close = Some(animated);
+    }
+}
+
+type ActionHandler = RefCell<Box<dyn FnMut(&mut ActionMenuHandle)>>;
+
+enum Item {
+    Action {
+        label: CString,
+        handler: ActionHandler,
+    },
+    Level {
+        label: CString,
+        level: ActionMenuLevel,
+    },
+}
+
+/// A level of an [`ActionMenu`], containing actions and nested levels.
+///
+/// ```ignore
+/// let root = ActionMenuLevel::new()
+///     .action("Reply", |_| reply())
+///     .level("Emoji", |level| {
+///         level
+///             .display_mode(ActionMenuDisplayMode::Thin)
+///             .action("👍", |_| send("👍"))
+///             .action("👎", |_| send("👎"))
+///     });
+/// ActionMenu::new(root).open();
+/// ```
+#[derive(Default)]
+pub struct ActionMenuLevel {
+    display_mode: ActionMenuDisplayMode,
+    items: Vec<Item>,
+}
+
+impl ActionMenuLevel {
+    pub fn new() -> ActionMenuLevel {
+        ActionMenuLevel::default()
+    }
+
+    pub fn display_mode(mut self, display_mode: ActionMenuDisplayMode) -> ActionMenuLevel {
+        self.display_mode = display_mode;
+        self
+    }
+
+    /// Add an item that calls `handler` when selected. The menu closes after
+    /// the handler returns, unless it is frozen.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `label` contains a nul byte.
+    pub fn action<F>(mut self, label: &str, handler: F) -> ActionMenuLevel
+    where
+        F: FnMut(&mut ActionMenuHandle) + 'static,
+    {
+        self.items.push(Item::Action {
+            label: CString::new(label).expect("Label contains a nul byte"),
+            handler: RefCell::new(Box::new(handler)),
+        });
+        self
+    }
+
+    /// Add an item that opens a nested level containing the items added by
+    /// `items`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `label` contains a nul byte.
+    pub fn level<F>(mut self, label: &str, items: F) -> ActionMenuLevel
+    where
+        F: FnOnce(ActionMenuLevel) -> ActionMenuLevel,
+    {
+        self.items.push(Item::Level {
+            label: CString::new(label).expect("Label contains a nul byte"),
+            level: items(ActionMenuLevel::new()),
+        });
+        self
+    }
+
+    /// Build the PebbleOS hierarchy for this level and its children, pointing
+    /// into `self` for labels and handlers.
+    ///
+    /// # Safety
+    ///
+    /// `self` must not be moved or dropped before the hierarchy is destroyed.
+    unsafe fn create_raw(&self) -> *mut RawActionMenuLevel {
+        let raw = unsafe { action_menu_level_create(self.items.len() as u16) };
+        assert!(!raw.is_null(), "Failed to create action menu level");
+        unsafe { action_menu_level_set_display_mode(raw, self.display_mode.raw()) };
+        for item in &self.items {
+            match item {
+                Item::Action { label, handler } => unsafe {
+                    action_menu_level_add_action(
+                        raw,
+                        label.as_ptr(),
+                        Some(perform_action),
+                        handler as *const ActionHandler as *mut c_void,
+                    );
+                },
+                Item::Level { label, level } => unsafe {
+                    action_menu_level_add_child(raw, level.create_raw(), label.as_ptr());
+                },
+            }
+        }
+        raw
+    }
+}
+
+// Owned by PebbleOS while the menu is open, and freed once it closes.
+struct OpenMenu {
+    root: ActionMenuLevel,
+    raw_root: *mut RawActionMenuLevel,
+    on_close: Option<Box<dyn FnOnce(bool)>>,
+}
+
+impl Drop for OpenMenu {
+    fn drop(&mut self) {
+        // Destroy the hierarchy before the labels and handlers it points to.
+        if !self.raw_root.is_null() {
+            unsafe { action_menu_hierarchy_destroy(self.raw_root, None, null_mut()) };
+        }
+    }
+}
+
+unsafe extern "C" fn perform_action(
+    action_menu: *mut RawActionMenu,
+    action: *const ActionMenuItem,
+    _context: *mut c_void,
+) {
+    let handler = unsafe { &*(action_menu_item_get_action_data(action) as *const ActionHandler) };
+    let mut handle = ActionMenuHandle {
+        raw: action_menu,
+        close: None,
+    };
+    if let Ok(mut handler) = handler.try_borrow_mut() {
+        handler(&mut handle);
+    }
+    // Closing can free the handler, so wait until it has returned.
+    if let Some(animated) = handle.close {
+        unsafe { action_menu_close(action_menu, animated) };
+    }
+}
+
+unsafe extern "C" fn did_close(
+    _action_menu: *mut RawActionMenu,
+    performed_action: *const ActionMenuItem,
+    context: *mut c_void,
+) {
+    let mut menu = unsafe { Box::from_raw(context as *mut OpenMenu) };
+    if let Some(on_close) = menu.on_close.take() {
+        on_close(!performed_action.is_null());
+    }
+}
+
+/// A full-screen menu of actions, closed when an action is performed or the
+/// back button is pressed.
+pub struct ActionMenu {
+    root: ActionMenuLevel,
+    background: Color,
+    foreground: Color,
+    align: ActionMenuAlign,
+    on_close: Option<Box<dyn FnOnce(bool)>>,
+}
+
+impl ActionMenu {
+    /// Prepare a menu starting at `root`, drawn white on black.
+    pub fn new(root: ActionMenuLevel) -> ActionMenu {
+        ActionMenu {
+            root,
+            background: Color::BLACK,
+            foreground: Color::WHITE,
+            align: ActionMenuAlign::default(),
+            on_close: None,
+        }
+    }
+
+    pub fn colors(mut self, background: Color, foreground: Color) -> ActionMenu {
+        self.background = background;
+        self.foreground = foreground;
+        self
+    }
+
+    pub fn align(mut self, align: ActionMenuAlign) -> ActionMenu {
+        self.align = align;
+        self
+    }
+
+    /// Call `handler` after the menu has closed, with whether an action was
+    /// performed.
+    pub fn on_close<F>(mut self, handler: F) -> ActionMenu
+    where
+        F: FnOnce(bool) + 'static,
+    {
+        self.on_close = Some(Box::new(handler));
+        self
+    }
+
+    /// Push the menu onto the window stack. Its levels and handlers are freed
+    /// once it closes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if PebbleOS is out of memory.
+    pub fn open(self) {
+        let mut menu = Box::new(OpenMenu {
+            root: self.root,
+            raw_root: null_mut(),
+            on_close: self.on_close,
+        });
+        menu.raw_root = unsafe { menu.root.create_raw() };
+        let raw_root = menu.raw_root;
+        let context = Box::into_raw(menu);
+        let mut config = ActionMenuConfig {
+            root_level: raw_root,
+            context: context as *mut c_void,
+            colors: ActionMenuColors {
+                background: self.background.raw(),
+                foreground: self.foreground.raw(),
+            },
+            will_close: None,
+            did_close: Some(did_close),
+            align: self.align.raw(),
+        };
+        let raw = unsafe { action_menu_open(&mut config) };
+        if raw.is_null() {
+            drop(unsafe { Box::from_raw(context) });
+            panic!("Failed to open action menu");
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! A window prompting for a number with the up and down buttons.
+
+use core::cell::Cell;
+use core::ffi::c_void;
+use core::mem;
+use core::ops::RangeInclusive;
+
+use rust_alloc::boxed::Box;
+use rust_alloc::ffi::CString;
+use rust_alloc::rc::Rc;
+
+use pebblesdk_sys::{
+    number_window_create, number_window_destroy, number_window_get_value, number_window_get_window,
+    number_window_set_label, number_window_set_max, number_window_set_min,
+    number_window_set_step_size, number_window_set_value, NumberWindow as RawNumberWindow,
+    NumberWindowCallbacks, Window as RawWindow,
+};
+
+use crate::timer::defer;
+
+type Handler = Box<dyn FnMut(i32)>;
+
+#[derive(Default)]
+struct Callbacks {
+    incremented: Cell<Option<Handler>>,
+    decremented: Cell<Option<Handler>>,
+    selected: Cell<Option<Handler>>,
+    // The number of handlers running.
+    dispatching: Cell<u32>,
+}
+
+fn dispatch<F>(number_window: *mut RawNumberWindow, context: *mut c_void, select: F)
+where
+    F: FnOnce(&Callbacks) -> &Cell<Option<Handler>>,
+{
+    // Hold a reference for the duration of the handler, in case it drops the
+    // window. The window itself is destroyed later, see `NumberWindow::drop`.
+    let callbacks = unsafe {
+        Rc::increment_strong_count(context as *const Callbacks);
+        Rc::from_raw(context as *const Callbacks)
+    };
+    let value = unsafe { number_window_get_value(number_window) };
+    let slot = select(&callbacks);
+    if let Some(mut handler) = slot.take() {
+        callbacks.dispatching.set(callbacks.dispatching.get() + 1);
+        handler(value);
+        callbacks.dispatching.set(callbacks.dispatching.get() - 1);
+        // Put the handler back, unless it was replaced while running.
+        let replacement = slot.take();
+        slot.set(replacement.or(Some(handler)));
+    }
+}
+
+unsafe extern "C" fn incremented(number_window: *mut RawNumberWindow, context: *mut c_void) {
+    dispatch(number_window, context, |callbacks| &callbacks.incremented);
+}
+
+unsafe extern "C" fn decremented(number_window: *mut RawNumberWindow, context: *mut c_void) {
+    dispatch(number_window, context, |callbacks| &callbacks.decremented);
+}
+
+unsafe extern "C" fn selected(number_window: *mut RawNumberWindow, context: *mut c_void) {
+    dispatch(number_window, context, |callbacks| &callbacks.selected);
+}
+
+/// A full-screen window for picking a number from a range, stepping with the
+/// up and down buttons and confirming with select.
+///
+/// ```ignore
+/// let mut minutes = NumberWindow::new("Snooze minutes", 1..=30, 1);
+/// minutes.set_value(10);
+/// minutes.on_selected(|value| settings.borrow_mut().snooze = value);
+/// ```
+pub struct NumberWindow {
+    raw: *mut RawNumberWindow,
+    label: CString,
+    range: RangeInclusive<i32>,
+    step: i32,
+    callbacks: Rc<Callbacks>,
+}
+
+impl NumberWindow {
+    /// Create a window titled `label`, starting at the start of `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `label` contains a nul byte, `range` is empty, `step` isn't
+    /// positive or is larger than `range`, or PebbleOS is out of memory.
+    pub fn new(label: &str, range: RangeInclusive<i32>, step: i32) -> NumberWindow {
+        let label = CString::new(label).expect("Label contains a nul byte");
+        let callbacks = Rc::new(Callbacks::default());
+        let raw = unsafe {
+            number_window_create(
+                label.as_ptr(),
+                NumberWindowCallbacks {
+                    incremented: Some(incremented),
+                    decremented: Some(decremented),
+                    selected: Some(selected),
+                },
+                Rc::as_ptr(&callbacks) as *mut c_void,
+            )
+        };
+        assert!(!raw.is_null(), "Failed to create number window");
+        let mut number_window = NumberWindow {
+            raw,
+            label,
+            range: 0..=0,
+            step: 1,
+            callbacks,
+        };
+        number_window.set_range(range);
+        number_window.set_step_size(step);
+        number_window.set_value(*number_window.range.start());
+        number_window
+    }
+
+    /// Get the underlying PebbleOS number window, for use with
+    /// [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut RawNumberWindow {
+        self.raw
+    }
+
+    /// Get the window to push onto the window stack.
+    pub fn window_ptr(&self) -> *mut RawWindow {
+        unsafe { number_window_get_window(self.raw) }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `label` contains a nul byte.
+    pub fn set_label(&mut self, label: &str) {
+        let label = CString::new(label).expect("Label contains a nul byte");
+        unsafe { number_window_set_label(self.raw, label.as_ptr()) };
+        // Only release the old label after PebbleOS has let go of it.
+        self.label = label;
+    }
+
+    pub fn range(&self) -> RangeInclusive<i32> {
+        self.range.clone()
+    }
+
+    /// Set the values that can be picked, clamping the current value and
+    /// step size into the new range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is empty.
+    pub fn set_range(&mut self, range: RangeInclusive<i32>) {
+        assert!(!range.is_empty(), "Number window range is empty");
+        unsafe {
+            // Widen before narrowing, so the minimum never passes the
+            // maximum along the way.
+            number_window_set_min(self.raw, i32::MIN);
+            number_window_set_max(self.raw, *range.end());
+            number_window_set_min(self.raw, *range.start());
+        }
+        self.range = range;
+        let step = self.step.min(self.max_step());
+        if step != self.step {
+            self.set_step_size(step);
+        }
+    }
+
+    // The difference between the ends of the range, or 1 if they're equal.
+    fn max_step(&self) -> i32 {
+        let span = *self.range.end() as i64 - *self.range.start() as i64;
+        span.clamp(1, i32::MAX as i64) as i32
+    }
+
+    pub fn step_size(&self) -> i32 {
+        self.step
+    }
+
+    /// Set how much each press of the up or down button changes the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` isn't positive, or is larger than the difference
+    /// between the ends of the range.
+    pub fn set_step_size(&mut self, step: i32) {
+        assert!(step > 0, "Number window step must be positive");
+        assert!(
+            step <= self.max_step(),
+            "Number window step is larger than its range"
+        );
+        unsafe { number_window_set_step_size(self.raw, step) };
+        self.step = step;
+    }
+
+    pub fn value(&self) -> i32 {
+        unsafe { number_window_get_value(self.raw) }
+    }
+
+    /// Set the current value, clamped into the window's range.
+    pub fn set_value(&mut self, value: i32) {
+        let value = value.clamp(*self.range.start(), *self.range.end());
+        unsafe { number_window_set_value(self.raw, value) }
+    }
+
+    /// Call `handler` with the new value after the up button is pressed.
+    pub fn on_incremented<F: FnMut(i32) + 'static>(&self, handler: F) {
+        self.callbacks.incremented.set(Some(Box::new(handler)));
+    }
+
+    /// Call `handler` with the new value after the down button is pressed.
+    pub fn on_decremented<F: FnMut(i32) + 'static>(&self, handler: F) {
+        self.callbacks.decremented.set(Some(Box::new(handler)));
+    }
+
+    /// Call `handler` with the value when the select button is pressed.
+    pub fn on_selected<F: FnMut(i32) + 'static>(&self, handler: F) {
+        self.callbacks.selected.set(Some(Box::new(handler)));
+    }
+}
+
+impl Drop for NumberWindow {
+    fn drop(&mut self) {
+        let raw = self.raw;
+        if self.callbacks.dispatching.get() == 0 {
+            unsafe { number_window_destroy(raw) };
+            return;
+        }
+        // Dropped from one of its own handlers, while PebbleOS is still
+        // handling the button press. Destroy it once control returns to the
+        // event loop.
+        let label = mem::take(&mut self.label);
+        let callbacks = self.callbacks.clone();
+        defer(move || {
+            unsafe { number_window_destroy(raw) };
+            drop((label, callbacks));
+        });
+    }
+}
@@ -0,0 +1,231 @@
+//! The stack of windows making up an app's navigation.
+//!
+//! Windows pushed through [`WindowStack`] are owned by it until PebbleOS
+//! unloads them, after any transition out has finished, so a window can't be
+//! destroyed while PebbleOS is still showing it.
+
+use core::any::Any;
+use core::marker::PhantomData;
+
+use rust_alloc::boxed::Box;
+use rust_alloc::vec::Vec;
+
+use pebblesdk_sys::{
+    window_set_window_handlers, window_stack_contains_window, window_stack_count,
+    window_stack_get_top_window, window_stack_pop, window_stack_pop_all, window_stack_push,
+    window_stack_remove, Window as RawWindow, WindowHandler, WindowHandlers,
+};
+
+use crate::cell::AppCell;
+use crate::timer::defer;
+use crate::window::number_window::NumberWindow;
+use crate::window::Window;
+
+/// A type that owns a PebbleOS window and can be pushed onto the
+/// [`WindowStack`].
+///
+/// # Safety
+///
+/// `window_ptr` must always return the same valid window, which lives until
+/// `self` is dropped. The stack sets the window's handlers when it is pushed,
+/// so they mustn't be changed afterwards. Pass any handlers the window needs
+/// to [`WindowStack::push_with_handlers`] instead.
+pub unsafe trait StackWindow: Any {
+    fn window_ptr(&self) -> *mut RawWindow;
+}
+
+unsafe impl StackWindow for Window {
+    fn window_ptr(&self) -> *mut RawWindow {
+        self.as_ptr()
+    }
+}
+
+unsafe impl StackWindow for NumberWindow {
+    fn window_ptr(&self) -> *mut RawWindow {
+        NumberWindow::window_ptr(self)
+    }
+}
+
+/// Refers to a window of type `W` that was pushed onto the [`WindowStack`].
+pub struct WindowHandle<W> {
+    raw: *mut RawWindow,
+    // Tells this window apart from a later one created at the same address.
+    id: u32,
+    _window: PhantomData<fn() -> W>,
+}
+
+impl<W> WindowHandle<W> {
+    /// Get the underlying PebbleOS window, for use with [`crate::sys`].
+    pub fn as_ptr(&self) -> *mut RawWindow {
+        self.raw
+    }
+}
+
+impl<W> Clone for WindowHandle<W> {
+    fn clone(&self) -> WindowHandle<W> {
+        *self
+    }
+}
+
+impl<W> Copy for WindowHandle<W> {}
+
+struct Entry {
+    raw: *mut RawWindow,
+    id: u32,
+    // The app's own unload handler, called before the window is dropped.
+    unload: WindowHandler,
+    window: Box<dyn Any>,
+}
+
+static WINDOWS: AppCell<Vec<Entry>> = AppCell::new(Vec::new());
+static NEXT_ID: AppCell<u32> = AppCell::new(0);
+
+fn find<W>(handle: WindowHandle<W>) -> bool {
+    WINDOWS
+        .borrow()
+        .iter()
+        .any(|entry| entry.raw == handle.raw && entry.id == handle.id)
+}
+
+// Called once the window has left the stack and finished any transition out,
+// whether it was popped through `WindowStack` or by the back button.
+unsafe extern "C" fn unload(raw: *mut RawWindow) {
+    let entry = {
+        let mut windows = WINDOWS.borrow_mut();
+        let Some(index) = windows.iter().position(|entry| entry.raw == raw) else {
+            return;
+        };
+        windows.remove(index)
+    };
+    if let Some(unload) = entry.unload {
+        unsafe { unload(raw) };
+    }
+    let window = entry.window;
+    // PebbleOS is still unloading the window, so destroy it once control
+    // returns to the event loop.
+    defer(move || drop(window));
+}
+
+/// The app's window stack, which owns the windows pushed onto it.
+///
+/// Windows are dropped once PebbleOS unloads them after they leave the stack,
+/// whether they were popped through `WindowStack` or by the back button.
+///
+/// ```ignore
+/// let handle = WindowStack::push(window, true);
+/// WindowStack::with(handle, |window| window.set_click_config(config));
+/// WindowStack::remove(handle, false);
+/// ```
+pub struct WindowStack;
+
+impl WindowStack {
+    /// Push `window` onto the top of the stack, taking ownership of it.
+    ///
+    /// This replaces any handlers already set on the window. Use
+    /// [`WindowStack::push_with_handlers`] to keep handling window events.
+    pub fn push<W: StackWindow>(window: W, animated: bool) -> WindowHandle<W> {
+        let handlers = WindowHandlers {
+            load: None,
+            appear: None,
+            disappear: None,
+            unload: None,
+        };
+        WindowStack::push_with_handlers(window, handlers, animated)
+    }
+
+    /// Push `window` like [`WindowStack::push`], calling `handlers` as
+    /// PebbleOS loads, shows, hides, and unloads it. The `unload` handler is
+    /// called just before the stack drops the window.
+    pub fn push_with_handlers<W: StackWindow>(
+        window: W,
+        handlers: WindowHandlers,
+        animated: bool,
+    ) -> WindowHandle<W> {
+        let raw = window.window_ptr();
+        let id = NEXT_ID.with(|next_id| {
+            let id = *next_id;
+            *next_id = next_id.wrapping_add(1);
+            id
+        });
+        WINDOWS.borrow_mut().push(Entry {
+            raw,
+            id,
+            unload: handlers.unload,
+            window: Box::new(window),
+        });
+        unsafe {
+            window_set_window_handlers(
+                raw,
+                WindowHandlers {
+                    unload: Some(unload),
+                    ..handlers
+                },
+            );
+            window_stack_push(raw, animated);
+        }
+        WindowHandle {
+            raw,
+            id,
+            _window: PhantomData,
+        }
+    }
+
+    /// Pop the top window. Returns false if the stack was empty.
+    ///
+    /// A window pushed through `WindowStack` isn't dropped right away, but
+    /// once PebbleOS unloads it after any transition out has finished.
+    pub fn pop(animated: bool) -> bool {
+        !unsafe { window_stack_pop(animated) }.is_null()
+    }
+
+    /// Pop every window.
+    pub fn pop_all(animated: bool) {
+        unsafe { window_stack_pop_all(animated) };
+    }
+
+    /// Remove the window referred to by `handle` from anywhere in the stack.
+    /// Returns false if it has already left the stack.
+    pub fn remove<W: StackWindow>(handle: WindowHandle<W>, animated: bool) -> bool {
+        find(handle) && unsafe { window_stack_remove(handle.raw, animated) }
+    }
+
+    /// Run `f` with the window referred to by `handle`, if it hasn't been
+    /// dropped yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` calls back into `WindowStack`, or pops or removes
+    /// windows.
+    pub fn with<W: StackWindow, R, F>(handle: WindowHandle<W>, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut W) -> R,
+    {
+        let mut windows = WINDOWS.borrow_mut();
+        let entry = windows
+            .iter_mut()
+            .find(|entry| entry.raw == handle.raw && entry.id == handle.id)?;
+        entry.window.downcast_mut::<W>().map(f)
+    }
+
+    /// True if the window referred to by `handle` is still on the stack.
+    pub fn contains<W>(handle: WindowHandle<W>) -> bool {
+        find(handle) && unsafe { window_stack_contains_window(handle.raw) }
+    }
+
+    /// True if the window referred to by `handle` is on top of the stack.
+    pub fn is_top<W>(handle: WindowHandle<W>) -> bool {
+        find(handle) && Self::top_window() == Some(handle.raw)
+    }
+
+    /// The window on top of the stack, whether or not it was pushed through
+    /// `WindowStack`.
+    pub fn top_window() -> Option<*mut RawWindow> {
+        let raw = unsafe { window_stack_get_top_window() };
+        (!raw.is_null()).then_some(raw)
+    }
+
+    /// The number of windows on the stack.
+    pub fn count() -> u32 {
+        unsafe { window_stack_count() }
+    }
+}